@@ -1,6 +1,9 @@
 /// Types to parse the command line arguments with the clap crate.
-use crate::{header, positive_less_than_32, string_no_empty, string_script, Verbosity};
-use clap::{Parser, Subcommand, ValueEnum};
+use crate::{
+    fallback, header, positive_less_than_32, sha256, string_no_empty, string_script, Fallback,
+    Verbosity,
+};
+use clap::{ArgGroup, Parser, Subcommand, ValueEnum};
 use std::cmp::Ord;
 
 #[derive(Parser)]
@@ -20,6 +23,12 @@ pub struct Args {
     #[arg(long, short, conflicts_with = "verbose")]
     pub quiet: bool,
 
+    /// Specify a profile to enable, only the services without profiles or with one of
+    /// the profiles enabled are used. Can be used multiple times, or with a comma
+    /// separated list
+    #[arg(long = "profile", value_name = "PROFILE", env = "COMPOSE_PROFILES", value_delimiter = ',', value_parser = string_no_empty)]
+    pub profiles: Vec<String>,
+
     /// Don't call docker compose to parse compose model
     #[arg(long)]
     pub no_docker: bool,
@@ -27,6 +36,16 @@ pub struct Args {
     /// Don't check model consistency - warning: may produce invalid Compose output
     #[arg(long, conflicts_with = "no_docker")]
     pub no_consistency: bool,
+
+    /// Interpolate variables like ${VAR} or ${VAR:-default} with the values
+    /// from the environment and the .env file
+    #[arg(long)]
+    pub interpolate: bool,
+
+    /// Specify an alternate environment file used with --interpolate,
+    /// instead of the .env file from the project directory
+    #[arg(long = "env-file", value_name = "PATH", requires = "interpolate")]
+    pub env_files: Vec<String>,
 }
 
 impl Args {
@@ -41,6 +60,8 @@ impl Args {
     }
 }
 
+// the commands are parsed only once, so the size of the variants doesn't matter
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand)]
 pub enum Commands {
     /// List objects found in the compose file: services, volumes, ...
@@ -51,36 +72,88 @@ pub enum Commands {
         #[arg(short, long, value_enum, default_value_t = Formats::Full, value_name = "FORMAT")]
         pretty: Formats,
     },
+    /// Compare objects found in the compose files, e.g. the environment
+    /// variables of two services
+    Diff {
+        #[command(subcommand)]
+        object: DiffObjects,
+
+        #[arg(short, long, value_enum, default_value_t = Formats::Full, value_name = "FORMAT")]
+        pretty: Formats,
+    },
     /// Parse, resolve and render compose file in canonical format
+    #[command(group(ArgGroup::new("lookup").args(["tags", "pin_digests"]).multiple(true)))]
     Config {
         /// Save to file (default to stdout)
         #[arg(short, long, value_name = "FILE")]
         output: Option<String>,
+        /// output only the services passed, removing the volumes, networks, configs and
+        /// secrets not used by them. Can be used multiple times, or with a comma separated list
+        #[arg(long = "services", value_name = "SERVICE", value_delimiter = ',', value_parser = string_no_empty)]
+        services: Vec<String>,
+        /// use with --services to include the services they depend on, recursively, with
+        /// depends_on, links, volumes_from or network_mode
+        #[arg(long, requires("services"))]
+        with_deps: bool,
         /// output image attributes in services with a tag passed instead of the one set in the file
-        /// if they exist locally or in the remote docker registry
-        #[arg(short, long, value_name = "TAG", value_parser = string_no_empty)]
-        tag: Option<String>,
+        /// if they exist locally or in the remote docker registry. Can be used multiple times,
+        /// or with a comma separated list of tags, the first tag found for each image is used
+        #[arg(short, long = "tag", value_name = "TAG", value_delimiter = ',', value_parser = string_no_empty)]
+        tags: Vec<String>,
         /// use with --tag to filter which images should be checked whether the
         /// tag exists or not locally or remotely.
         /// Currently only regex=EXPR or regex!=EXPR are supported
-        #[arg(long, value_name = "FILTER", requires("tag"), value_parser = string_no_empty)]
+        #[arg(long, value_name = "FILTER", requires("tags"), value_parser = string_no_empty)]
         tag_filter: Option<String>,
-        /// ignore unauthorized errors from docker when fetching remote tags info
-        #[arg(long, requires("tag"))]
+        /// output image attributes in services with an immutable reference `repo@sha256:...`,
+        /// looking for the digest of the image locally and then in the remote docker registry.
+        /// The original image is kept in the "pose.image" label of the service
+        #[arg(long)]
+        pin_digests: bool,
+        /// ignore unauthorized errors from the docker registry when fetching remote tags info
+        #[arg(long, requires("lookup"))]
         ignore_unauthorized: bool,
         /// Don't slugify the value from --tag
-        #[arg(long, requires("tag"))]
+        #[arg(long, requires("tags"))]
         no_slug: bool,
-        /// only check --tag TAG with the local docker registry
-        #[arg(long, requires("tag"))]
+        /// only check --tag TAG or the digests with the local docker registry
+        #[arg(long, requires("lookup"))]
         offline: bool,
         /// outputs in stderr the progress of fetching the tags info, similar to --verbose,
         /// but without all the other details --verbose adds
-        #[arg(long, requires("tag"))]
+        #[arg(long, requires("lookup"))]
         progress: bool,
         /// max number of threads used to fetch remote images info
-        #[arg(long, value_name = "NUM", default_value_t = 8, value_parser = positive_less_than_32, requires("tag"))]
+        #[arg(long, value_name = "NUM", default_value_t = 8, value_parser = positive_less_than_32, requires("lookup"))]
         threads: u8,
+        /// don't use the cache of the remote tags info, by default the tags found are cached
+        /// for one day, and the tags not found for 5 minutes
+        #[arg(long, requires("tags"))]
+        no_cache: bool,
+        /// directory where to store the cache of the remote tags info
+        /// [default: $XDG_CACHE_HOME/pose or ~/.cache/pose]
+        #[arg(long, value_name = "DIR", requires("tags"), conflicts_with("no_cache"), value_parser = string_no_empty)]
+        cache_dir: Option<String>,
+    },
+    /// Validate the compose files against the Compose specification, reporting
+    /// every violation found with the line where it's located. Unless --no-docker
+    /// is used, the model consistency is checked as well with docker compose
+    Validate,
+    /// Check the compose model against best practices, e.g. images without
+    /// a tag, privileged services or references to resources not defined
+    Lint {
+        #[arg(short, long, value_enum, default_value_t = LintFormats::Text, value_name = "FORMAT")]
+        format: LintFormats,
+        /// config file with the rules disabled [default: .pose-lint.yaml
+        /// in the project directory, if exists]
+        #[arg(long, value_name = "FILE", value_parser = string_no_empty)]
+        config: Option<String>,
+    },
+    /// Outputs the services dependency graph built from the depends_on attributes,
+    /// by default the order in which the services have to be started
+    Graph {
+        #[arg(short, long, value_enum, default_value_t = GraphFormats::Order, value_name = "FORMAT")]
+        format: GraphFormats,
     },
     /// Outputs a slug version of the text passed, or the slug version of the
     /// current branch.
@@ -98,8 +171,12 @@ pub enum Commands {
     /// Download a file from an HTTP URL, if the resource doesn't exist, fallback
     /// to another URL generated editing the URL given with a script provided in the
     /// form of "text-to-replace:replacer".
+    /// The proxy is taken from the environment variables HTTPS_PROXY, HTTP_PROXY
+    /// or ALL_PROXY, except for the hosts listed in NO_PROXY.
     Get {
-        /// The URL where the file is located
+        /// The URL where the file is located. It can have the placeholders
+        /// `{branch}` and `{slug}`, replaced with the current git branch name
+        /// and its slug version, e.g. `https://server.com/repo/{slug}/compose.yml`
         #[arg(value_parser = string_no_empty)]
         url: String,
         /// if request to URL responds back with HTTP 404, create a second URL
@@ -110,6 +187,12 @@ pub enum Commands {
         /// if not found, will try at https://server.com/repo/master/compose.yml
         #[arg(value_parser = string_script)]
         script: Option<(String, String)>,
+        /// If the file is not found, try with the URL generated with this
+        /// fallback: a script like the SCRIPT argument, or a value to fill the
+        /// placeholders of the URL, e.g. `--fallback develop --fallback main`.
+        /// Can be repeated, each fallback is tried in order
+        #[arg(long, value_name = "FALLBACK", value_parser = fallback)]
+        fallback: Vec<Fallback>,
         /// Save to file (default use the same filename set in the url)
         #[arg(short, long, value_name = "FILE")]
         output: Option<String>,
@@ -124,6 +207,54 @@ pub enum Commands {
         /// HTTP header to include in the request
         #[arg(short = 'H', long = "header", value_name = "HEADER", value_parser = header)]
         headers: Vec<(String, String)>,
+        /// User and password to authenticate with the server, in the form `USER:PASSWORD`
+        #[arg(short, long, value_name = "USER:PASSWORD", value_parser = string_script)]
+        user: Option<(String, String)>,
+        /// Name of the environment variable with the token to authenticate
+        /// with the server, sent as a bearer token, so the token is not exposed
+        /// in the command line
+        #[arg(long, value_name = "VAR", conflicts_with = "user")]
+        bearer_token_env: Option<String>,
+        /// Use the credentials of the servers from the $NETRC or ~/.netrc file, when no
+        /// other credentials are passed. They are only sent with HTTPS, or to localhost
+        #[arg(long)]
+        netrc: bool,
+        /// Like --netrc, but with the credentials from the file passed
+        #[arg(long, value_name = "FILE", value_parser = string_no_empty)]
+        netrc_file: Option<String>,
+        /// PEM file with the certificates of the CAs to verify the server,
+        /// e.g. the CA of an internal network, used instead of the public CAs
+        #[arg(long, value_name = "FILE", value_parser = string_no_empty)]
+        cacert: Option<String>,
+        /// PEM file with the client certificate to authenticate with the
+        /// server (mTLS), it can also include the private key
+        #[arg(long, value_name = "FILE", value_parser = string_no_empty)]
+        cert: Option<String>,
+        /// PEM file with the private key of the client certificate
+        #[arg(long, value_name = "FILE", value_parser = string_no_empty, requires = "cert")]
+        key: Option<String>,
+        /// Number of times to retry the download if it fails with a transport error,
+        /// like a connection closed in the middle of the transfer, or with an HTTP 5xx
+        /// error. The download is resumed if the server supports range requests
+        #[arg(long, value_name = "NUM", default_value_t = 0)]
+        retry: u16,
+        /// Seconds to wait before the first retry, the time is doubled in each retry
+        #[arg(long, value_name = "SECONDS", default_value_t = 1, requires("retry"))]
+        retry_delay: u16,
+        /// Verify the SHA-256 checksum of the file downloaded. The file is
+        /// saved only if the checksum matches
+        #[arg(long, value_name = "HEX", value_parser = sha256)]
+        sha256: Option<String>,
+        /// URL of a file with the SHA-256 checksum of the file to download,
+        /// in the format of the `sha256sum` command, to verify the file downloaded
+        #[arg(long, value_name = "URL", conflicts_with = "sha256")]
+        checksum_url: Option<String>,
+        /// Always download the file. By default the ETag and Last-Modified headers
+        /// of the file downloaded are stored in a sidecar file next to it
+        /// (`.FILE.pose-cache`), and sent in the next download of the same URL,
+        /// so the file is not downloaded again if it wasn't modified
+        #[arg(long)]
+        no_cache: bool,
     },
 }
 
@@ -139,34 +270,52 @@ pub enum Objects {
         #[arg(short, long)]
         filter: Option<String>,
         /// print images with the tag passed instead of the one set in the file if they exist
-        /// locally or in the remote docker registry
-        #[arg(short, long, value_name = "TAG", value_parser = string_no_empty)]
-        tag: Option<String>,
+        /// locally or in the remote docker registry. Can be used multiple times, or with
+        /// a comma separated list of tags, the first tag found for each image is used
+        #[arg(short, long = "tag", value_name = "TAG", value_delimiter = ',', value_parser = string_no_empty)]
+        tags: Vec<String>,
         /// use with --tag to filter which images should be checked whether the tag exists
         /// or not, but images that don't match the filter are not filtered out from the list
         /// printed, only printed with the tag they have in the compose file.
         /// Currently only regex=EXPR or regex!=EXPR are supported
-        #[arg(long, value_name = "FILTER", requires("tag"), value_parser = string_no_empty)]
+        #[arg(long, value_name = "FILTER", requires("tags"), value_parser = string_no_empty)]
         tag_filter: Option<String>,
-        /// ignore unauthorized errors from docker when fetching remote tags info
-        #[arg(long, requires("tag"))]
+        /// ignore unauthorized errors from the docker registry when fetching remote tags info
+        #[arg(long, requires("tags"))]
         ignore_unauthorized: bool,
         /// Don't slugify the value from --tag
-        #[arg(long, requires("tag"))]
+        #[arg(long, requires("tags"))]
         no_slug: bool,
         /// only check --tag TAG with the local docker registry
-        #[arg(long, requires("tag"))]
+        #[arg(long, requires("tags"))]
         offline: bool,
         /// outputs in stderr the progress of fetching the tags info, similar to --verbose
         /// but without all the other details --verbose adds
-        #[arg(long, requires("tag"))]
+        #[arg(long, requires("tags"))]
         progress: bool,
         /// max number of threads used to fetch images info
-        #[arg(long, value_name = "NUM", default_value_t = 8, value_parser = positive_less_than_32, requires("tag"))]
+        #[arg(long, value_name = "NUM", default_value_t = 8, value_parser = positive_less_than_32, requires("tags"))]
         threads: u8,
+        /// don't use the cache of the remote tags info, by default the tags found are cached
+        /// for one day, and the tags not found for 5 minutes
+        #[arg(long, requires("tags"))]
+        no_cache: bool,
+        /// directory where to store the cache of the remote tags info
+        /// [default: $XDG_CACHE_HOME/pose or ~/.cache/pose]
+        #[arg(long, value_name = "DIR", requires("tags"), conflicts_with("no_cache"), value_parser = string_no_empty)]
+        cache_dir: Option<String>,
     },
     /// List service's depends_on
-    Depends { service: String },
+    Depends {
+        service: String,
+        /// include the transitive dependencies, listed in the order
+        /// they have to be started
+        #[arg(short, long)]
+        recursive: bool,
+        /// list the services that depend on the service instead
+        #[arg(long)]
+        reverse: bool,
+    },
     /// List volumes
     Volumes,
     /// List networks
@@ -175,12 +324,58 @@ pub enum Objects {
     Configs,
     /// List secrets
     Secrets,
+    /// List the volumes mounted by the services, warning about bind mounts with a
+    /// host path that doesn't exist, and named volumes not declared
+    Mounts {
+        /// list only the volumes mounted by the service
+        #[arg(value_parser = string_no_empty)]
+        service: Option<String>,
+    },
+    /// List the ports of the services, failing if the same
+    /// host port is published more than once
+    Ports,
     /// List profiles
-    Profiles,
+    Profiles {
+        /// list the services enabled by each profile
+        #[arg(long)]
+        services: bool,
+    },
     /// List service's environment variables
     Envs {
         #[arg(value_parser = string_no_empty)]
         service: String,
+        /// include the variables from the files set in the env_file attribute
+        /// of the service, the variables from the environment attribute take precedence
+        #[arg(long)]
+        with_env_files: bool,
+        /// annotate each variable with the attribute or the file it comes from
+        #[arg(long)]
+        show_source: bool,
+    },
+}
+
+#[derive(Subcommand, PartialEq)]
+pub enum DiffObjects {
+    /// Compare the environment variables of two services, or of the same service
+    /// in another compose file, listing the variables added (+), removed (-)
+    /// or changed (~) in the second one
+    Envs {
+        #[arg(value_parser = string_no_empty)]
+        service: String,
+        /// service to compare with, by default the same service
+        /// from the compose file passed with --against
+        #[arg(value_parser = string_no_empty, required_unless_present("against"))]
+        other_service: Option<String>,
+        /// compose file to compare with, e.g. the file of another environment
+        #[arg(long, value_name = "FILE", value_parser = string_no_empty)]
+        against: Option<String>,
+        /// include the variables from the files set in the env_file attribute
+        /// of the services
+        #[arg(long)]
+        with_env_files: bool,
+        /// mask the values of the variables that look like secrets, e.g. DB_PASSWORD
+        #[arg(long)]
+        mask: bool,
     },
 }
 
@@ -188,4 +383,33 @@ pub enum Objects {
 pub enum Formats {
     Full,
     Oneline,
+    Json,
+    Yaml,
+    Tsv,
+}
+
+impl Formats {
+    /// Whether the format outputs records in a structured way,
+    /// instead of the plain text format.
+    pub fn is_structured(&self) -> bool {
+        matches!(self, Formats::Json | Formats::Yaml | Formats::Tsv)
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, strum_macros::Display)]
+pub enum GraphFormats {
+    /// Services in the order they have to be started, fails if there is a cycle
+    Order,
+    /// Graphviz DOT format
+    Dot,
+    /// Mermaid flowchart
+    Mermaid,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, strum_macros::Display)]
+pub enum LintFormats {
+    /// One line per violation, with the file and line where it's located
+    Text,
+    /// SARIF 2.1.0 report, supported by code review tools
+    Sarif,
 }
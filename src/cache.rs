@@ -0,0 +1,237 @@
+/// On-disk caches: the tags found or not found in the remote registries,
+/// so consecutive executions don't check the same images again, and the
+/// validators of the files downloaded, to download them only if modified.
+use crate::http::get_file_sha256;
+use crate::Verbosity;
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::{env, fs, process};
+
+const CACHE_FILE: &str = "tags.json";
+
+/// Time the images found are cached.
+const POSITIVE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+/// Time the images not found are cached, shorter than the positive
+/// TTL because the images may be pushed at any moment, e.g. by the
+/// CI pipeline of a feature branch.
+const NEGATIVE_TTL: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    found: bool,
+    /// seconds since the Unix epoch
+    checked_at: u64,
+}
+
+/// Cache of tag lookups keyed by `registry/repo:tag`,
+/// safe to share between threads.
+pub struct TagCache {
+    path: PathBuf,
+    entries: Mutex<HashMap<String, CacheEntry>>,
+    positive_ttl: Duration,
+    negative_ttl: Duration,
+    verbosity: Verbosity,
+}
+
+impl TagCache {
+    /// Load the cache stored in the directory passed, if the cache file
+    /// doesn't exist or can't be read the cache starts empty.
+    pub fn load(dir: &Path, verbosity: Verbosity) -> Self {
+        let path = dir.join(CACHE_FILE);
+        let entries = read_entries(&path).unwrap_or_else(|e| {
+            if !matches!(verbosity, Verbosity::Quiet) {
+                eprintln!("{}: {}, ignoring the cache", "WARN".yellow(), e);
+            }
+            HashMap::new()
+        });
+        Self {
+            path,
+            entries: Mutex::new(entries),
+            positive_ttl: POSITIVE_TTL,
+            negative_ttl: NEGATIVE_TTL,
+            verbosity,
+        }
+    }
+
+    /// Set the time the results are considered valid, for
+    /// images found (`positive`) and not found (`negative`).
+    pub fn with_ttl(self, positive: Duration, negative: Duration) -> Self {
+        Self {
+            positive_ttl: positive,
+            negative_ttl: negative,
+            ..self
+        }
+    }
+
+    /// Default directory of the cache: `$XDG_CACHE_HOME/pose` or `~/.cache/pose`.
+    pub fn default_dir() -> Option<PathBuf> {
+        match env::var("XDG_CACHE_HOME") {
+            Ok(dir) if !dir.is_empty() => Some(Path::new(&dir).join("pose")),
+            _ => env::var("HOME")
+                .ok()
+                .map(|home| Path::new(&home).join(".cache").join("pose")),
+        }
+    }
+
+    /// Whether the image was found or not, if the result is cached and not expired.
+    pub fn get(&self, key: &str) -> Option<bool> {
+        let entries = self.entries.lock().unwrap();
+        let entry = entries.get(key)?;
+        match self.is_expired(entry, now()) {
+            true => None,
+            false => Some(entry.found),
+        }
+    }
+
+    pub fn insert(&self, key: &str, found: bool) {
+        let entry = CacheEntry {
+            found,
+            checked_at: now(),
+        };
+        self.entries.lock().unwrap().insert(key.to_string(), entry);
+    }
+
+    /// Store the cache, merged with the entries stored by other executions
+    /// in the meantime, and without the expired entries. The file is written
+    /// atomically, so concurrent executions never read a partial file.
+    pub fn save(&self) {
+        if let Err(e) = self.write() {
+            if !matches!(self.verbosity, Verbosity::Quiet) {
+                eprintln!("{}: {}", "WARN".yellow(), e);
+            }
+        }
+    }
+
+    fn write(&self) -> Result<(), String> {
+        let mut entries = read_entries(&self.path).unwrap_or_default();
+        entries.extend(self.entries.lock().unwrap().clone());
+        let now = now();
+        entries.retain(|_, entry| !self.is_expired(entry, now));
+        let content = serde_json::to_string(&entries)
+            .map_err(|e| format!("serializing tags cache: {}", e))?;
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("creating cache directory '{}': {}", dir.display(), e))?;
+        }
+        let tmp_path = self
+            .path
+            .with_extension(format!("json.{}.tmp", process::id()));
+        fs::write(&tmp_path, content)
+            .and_then(|_| fs::rename(&tmp_path, &self.path))
+            .map_err(|e| {
+                let _ = fs::remove_file(&tmp_path);
+                format!("writing tags cache '{}': {}", self.path.display(), e)
+            })?;
+        if matches!(self.verbosity, Verbosity::Verbose) {
+            eprintln!(
+                "{}: tags cache saved in {}",
+                "DEBUG".green(),
+                self.path.display()
+            );
+        }
+        Ok(())
+    }
+
+    fn is_expired(&self, entry: &CacheEntry, now: u64) -> bool {
+        let ttl = match entry.found {
+            true => self.positive_ttl,
+            false => self.negative_ttl,
+        };
+        now.saturating_sub(entry.checked_at) >= ttl.as_secs()
+    }
+}
+
+/// Validators of a file downloaded, stored in a sidecar file next to it,
+/// so the next download from the same URL can be a conditional request.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DownloadCache {
+    pub url: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    /// SHA-256 checksum of the file saved, to detect changes made to it after the download
+    pub sha256: Option<String>,
+}
+
+impl DownloadCache {
+    /// Path of the sidecar file of the file passed.
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use docker_pose::DownloadCache;
+    ///
+    /// assert_eq!(
+    ///     DownloadCache::sidecar_path("ci/compose.yaml"),
+    ///     PathBuf::from("ci/.compose.yaml.pose-cache")
+    /// );
+    /// ```
+    pub fn sidecar_path(filename: &str) -> PathBuf {
+        let path = Path::new(filename);
+        let name = path
+            .file_name()
+            .and_then(|f| f.to_str())
+            .unwrap_or(filename);
+        path.with_file_name(format!(".{}.pose-cache", name))
+    }
+
+    /// Load the validators of the file passed, `None` if the file
+    /// or its sidecar file don't exist, the sidecar can't be read, or
+    /// the file was edited or replaced after it was downloaded, so
+    /// the server is not asked whether a file it didn't send was modified.
+    pub fn load(filename: &str) -> Option<Self> {
+        if !Path::new(filename).is_file() {
+            return None;
+        }
+        let content = fs::read_to_string(Self::sidecar_path(filename)).ok()?;
+        let cache: Self = serde_json::from_str(&content).ok()?;
+        let sha256 = get_file_sha256(Path::new(filename)).ok()?;
+        match cache.sha256.as_ref() == Some(&sha256) {
+            true => Some(cache),
+            false => None,
+        }
+    }
+
+    /// Whether the file has validators to send a conditional request.
+    pub fn has_validators(&self) -> bool {
+        self.etag.is_some() || self.last_modified.is_some()
+    }
+
+    /// Store the validators in the sidecar file of the file passed,
+    /// or remove the sidecar file if there are no validators to store.
+    pub fn save(&self, filename: &str) -> Result<(), String> {
+        let path = Self::sidecar_path(filename);
+        if !self.has_validators() {
+            return match fs::remove_file(&path) {
+                Err(e) if e.kind() != ErrorKind::NotFound => {
+                    Err(format!("removing '{}': {}", path.display(), e))
+                }
+                _ => Ok(()),
+            };
+        }
+        let content = serde_json::to_string(self)
+            .map_err(|e| format!("serializing download cache: {}", e))?;
+        fs::write(&path, content)
+            .map_err(|e| format!("writing download cache '{}': {}", path.display(), e))
+    }
+}
+
+fn read_entries(path: &Path) -> Result<HashMap<String, CacheEntry>, String> {
+    if !path.is_file() {
+        return Ok(HashMap::new());
+    }
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("reading tags cache '{}': {}", path.display(), e))?;
+    serde_json::from_str(&content)
+        .map_err(|e| format!("parsing tags cache '{}': {}", path.display(), e))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
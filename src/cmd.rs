@@ -1,11 +1,11 @@
 /// Methods to perform command-line tool calls.
 /// Used by pose to make calls to the `docker` command
 /// and the `git` command.
-use crate::Verbosity;
+use crate::{PoseError, Verbosity};
 use colored::Colorize;
+use std::io;
 use std::io::Write;
 use std::process::{Command, Output, Stdio};
-use std::{io, process};
 
 /// get a string that should be identical to a command-line tool
 /// call made by `std::process::Command`.
@@ -44,45 +44,39 @@ pub fn cmd_call(
     command = command.stdout(Stdio::piped()).stderr(Stdio::piped());
     let output = command.output()?; // an error not from the command but trying to execute it
     if output_stdout {
-        cmd_write_stdout(bin, &output.stdout);
+        cmd_write_stdout(&output.stdout)?;
     }
     if output_stderr {
-        cmd_write_stderr(bin, &output.stderr);
+        cmd_write_stderr(&output.stderr)?;
     }
     Ok(output)
 }
 
-pub fn cmd_write_stderr(bin: &str, stderr: &[u8]) {
-    io::stderr().write_all(stderr).unwrap_or_else(|e| {
-        eprintln!("{}: writing {} stderr: {}", "ERROR".red(), bin, e);
-        process::exit(151);
-    });
+pub fn cmd_write_stderr(stderr: &[u8]) -> io::Result<()> {
+    io::stderr().write_all(stderr)
 }
 
-pub fn cmd_write_stdout(bin: &str, stdout: &[u8]) {
-    io::stdout().write_all(stdout).unwrap_or_else(|e| {
-        eprintln!("{}: writing {} stdout: {}", "ERROR".red(), bin, e);
-        process::exit(151);
-    });
+pub fn cmd_write_stdout(stdout: &[u8]) -> io::Result<()> {
+    io::stdout().write_all(stdout)
 }
 
-pub fn cmd_exit_code(bin: &str, output: &Output) -> i32 {
-    output.status.code().unwrap_or_else(|| {
-        eprintln!("{}: {} process terminated by signal", "ERROR".red(), bin);
-        process::exit(10)
-    })
+pub fn cmd_exit_code(bin: &str, output: &Output) -> Result<i32, PoseError> {
+    output
+        .status
+        .code()
+        .ok_or_else(|| PoseError::Other(format!("{} process terminated by signal", bin)))
 }
 
 /// Get the string from the `output` that was generated
-/// by a call to `bin bin_cmd`. If was not successful
-/// print the error and exit. If `quiet` is `false`
-/// also print any warning detected (stderr output).
-pub fn cmd_get_success_output_or_fail(
+/// by a call to `bin bin_cmd`, or the error if it was not
+/// successful. If `quiet` is `false` also print any
+/// warning detected (stderr output).
+pub fn cmd_get_success_output(
     bin: &str,
     bin_cmd: &str,
     output: Output,
     quiet: bool,
-) -> String {
+) -> Result<String, PoseError> {
     match output.status.success() {
         true => {
             // success !
@@ -93,23 +87,21 @@ pub fn cmd_get_success_output_or_fail(
                     "WARN".yellow(),
                     bin_cmd
                 );
-                cmd_write_stderr(bin, &output.stderr);
+                cmd_write_stderr(&output.stderr).map_err(|e| {
+                    PoseError::CommandCall(format!("writing {} stderr: {}", bin, e))
+                })?;
             }
-            String::from_utf8(output.stdout).unwrap_or_else(|e| {
-                eprintln!(
-                    "{}: deserializing {} {} output: {}",
-                    "ERROR".red(),
-                    bin,
-                    bin_cmd,
-                    e,
-                );
-                process::exit(17);
+            String::from_utf8(output.stdout).map_err(|e| {
+                PoseError::Utf8(format!("deserializing {} {} output: {}", bin, bin_cmd, e))
             })
         }
-        false => {
-            eprintln!("{}: calling {}", "ERROR".red(), bin_cmd);
-            cmd_write_stderr(bin, &output.stderr);
-            process::exit(cmd_exit_code(bin, &output));
-        }
+        false => Err(PoseError::Command(
+            format!(
+                "calling {}\n{}",
+                bin_cmd,
+                String::from_utf8_lossy(&output.stderr).trim_end()
+            ),
+            cmd_exit_code(bin, &output)?,
+        )),
     }
 }
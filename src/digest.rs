@@ -0,0 +1,43 @@
+/// Resolution of images to immutable references with the form `repo@sha256:...`,
+/// from the output of `docker image inspect`.
+use crate::ImageRef;
+use serde_json::Value;
+
+/// Get the digest of the image from the `RepoDigests` attribute of the
+/// `docker image inspect` output, only if the digest belongs to the same
+/// repository of the image, otherwise the digest may not be found in
+/// the remote registry.
+///
+/// ```
+/// use docker_pose::{get_repo_digest, ImageRef};
+///
+/// let inspect = r#"[{"RepoDigests": ["postgres@sha256:abc"]}]"#;
+/// let image = ImageRef::parse("docker.io/library/postgres:15").unwrap();
+/// assert_eq!(get_repo_digest(inspect, &image), Some("sha256:abc".to_string()));
+///
+/// let image = ImageRef::parse("mrsarm/postgres:15").unwrap();
+/// assert_eq!(get_repo_digest(inspect, &image), None);
+/// ```
+pub fn get_repo_digest(inspect_output: &str, image: &ImageRef) -> Option<String> {
+    let inspect: Value = serde_json::from_str(inspect_output).ok()?;
+    let name = image.name();
+    let name = normalize_name(&name);
+    inspect
+        .as_array()?
+        .iter()
+        .flat_map(|img| img.get("RepoDigests").and_then(|d| d.as_array()))
+        .flatten()
+        .flat_map(|d| d.as_str()?.split_once('@'))
+        .find(|(repo, _)| normalize_name(repo) == name)
+        .map(|(_, digest)| digest.to_string())
+}
+
+/// Remove the default registry and namespace from the name, so
+/// `docker.io/library/postgres` and `postgres` are considered the same.
+fn normalize_name(name: &str) -> &str {
+    let name = name
+        .strip_prefix("docker.io/")
+        .or_else(|| name.strip_prefix("index.docker.io/"))
+        .unwrap_or(name);
+    name.strip_prefix("library/").unwrap_or(name)
+}
@@ -1,5 +1,7 @@
 use crate::verbose::Verbosity;
-use crate::{cmd_call, cmd_call_to_string, cmd_exit_code, cmd_write_stderr, cmd_write_stdout};
+use crate::{
+    cmd_call, cmd_call_to_string, cmd_exit_code, cmd_write_stderr, cmd_write_stdout, PoseError,
+};
 
 use std::env::var;
 use std::io;
@@ -64,10 +66,15 @@ impl DockerCommand {
     pub fn call_compose_config(
         &self,
         filenames: &[&str],
+        profiles: &[String],
         no_consistency: bool,
         output_stdout: bool,
         output_stderr: bool,
     ) -> io::Result<Output> {
+        let args = profiles
+            .iter()
+            .flat_map(|profile| ["--profile", profile.as_str()])
+            .collect::<Vec<_>>();
         let mut cmd_args = Vec::new();
         cmd_args.push("--no-interpolate");
         cmd_args.push("--no-normalize");
@@ -77,7 +84,7 @@ impl DockerCommand {
         self.call_compose_cmd(
             "config",
             filenames,
-            &Vec::default(),
+            &args,
             &cmd_args,
             output_stdout,
             output_stderr,
@@ -101,15 +108,15 @@ impl DockerCommand {
         self.call_cmd(&["pull", image], output_stdout, output_stderr)
     }
 
-    pub fn write_stderr(&self, stderr: &[u8]) {
-        cmd_write_stderr(&self.docker_bin, stderr);
+    pub fn write_stderr(&self, stderr: &[u8]) -> io::Result<()> {
+        cmd_write_stderr(stderr)
     }
 
-    pub fn write_stdout(&self, stdout: &[u8]) {
-        cmd_write_stdout(&self.docker_bin, stdout);
+    pub fn write_stdout(&self, stdout: &[u8]) -> io::Result<()> {
+        cmd_write_stdout(stdout)
     }
 
-    pub fn exit_code(&self, output: &Output) -> i32 {
+    pub fn exit_code(&self, output: &Output) -> Result<i32, PoseError> {
         cmd_exit_code(&self.docker_bin, output)
     }
 }
@@ -0,0 +1,102 @@
+/// Parser of `.env` files, used to load the variables for
+/// interpolation and the `env_file` attributes of the services.
+use regex::Regex;
+use std::fs;
+use std::path::Path;
+
+lazy_static! {
+    static ref ENV_KEY_REGEX: Regex = Regex::new(r"^[a-zA-Z_][\w.-]*$").unwrap();
+}
+
+/// Parse the content of a `.env` file, returning the list of variables
+/// in the same order they are defined. Variables declared without
+/// the `=` symbol have `None` as value.
+///
+/// Lines starting with `#` are comments, the `export` prefix is ignored,
+/// values between single quotes are taken literally, values between double
+/// quotes support the escape sequences `\n`, `\t`, `\"` and `\\`, and
+/// unquoted values are trimmed and can have comments after ` #`.
+///
+/// ```
+/// use docker_pose::parse_dotenv;
+///
+/// let content = "PORT=8000\n\
+///     export TITLE=\"App 1\"\n\
+///     DESC='Literal $VAR' # comment\n\
+///     DEBUG=true # comment\n\
+///     UNDEFINED";
+/// assert_eq!(
+///     parse_dotenv(content),
+///     Ok(vec![
+///         ("PORT".to_string(), Some("8000".to_string())),
+///         ("TITLE".to_string(), Some("App 1".to_string())),
+///         ("DESC".to_string(), Some("Literal $VAR".to_string())),
+///         ("DEBUG".to_string(), Some("true".to_string())),
+///         ("UNDEFINED".to_string(), None),
+///     ])
+/// );
+/// assert_eq!(parse_dotenv("A='abc"), Err("line 1: unterminated quoted value".to_string()));
+/// assert_eq!(parse_dotenv("A B=1"), Err("line 1: invalid variable name 'A B'".to_string()));
+/// ```
+pub fn parse_dotenv(content: &str) -> Result<Vec<(String, Option<String>)>, String> {
+    let mut vars = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line).trim_start();
+        let (key, value) = match line.split_once('=') {
+            Some((key, value)) => (key.trim(), Some(value.trim_start())),
+            None => (line, None),
+        };
+        if !ENV_KEY_REGEX.is_match(key) {
+            return Err(format!("line {}: invalid variable name '{}'", i + 1, key));
+        }
+        let value = match value {
+            None => None,
+            Some(val) => Some(
+                parse_dotenv_value(val)
+                    .ok_or_else(|| format!("line {}: unterminated quoted value", i + 1))?,
+            ),
+        };
+        vars.push((key.to_string(), value));
+    }
+    Ok(vars)
+}
+
+fn parse_dotenv_value(value: &str) -> Option<String> {
+    if let Some(val) = value.strip_prefix('\'') {
+        let end = val.find('\'')?;
+        return Some(val[..end].to_string());
+    }
+    if let Some(val) = value.strip_prefix('"') {
+        let mut result = String::with_capacity(val.len());
+        let mut chars = val.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => return Some(result),
+                '\\' => match chars.next()? {
+                    'n' => result.push('\n'),
+                    't' => result.push('\t'),
+                    'r' => result.push('\r'),
+                    other => result.push(other),
+                },
+                _ => result.push(c),
+            }
+        }
+        return None;
+    }
+    let val = match value.find(" #") {
+        Some(pos) => &value[..pos],
+        None => value,
+    };
+    Some(val.trim_end().to_string())
+}
+
+/// Read and parse a `.env` file, see `parse_dotenv`.
+pub fn read_dotenv(path: &Path) -> Result<Vec<(String, Option<String>)>, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("reading env file '{}': {}", path.display(), e))?;
+    parse_dotenv(&content).map_err(|e| format!("parsing env file '{}': {}", path.display(), e))
+}
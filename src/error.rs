@@ -0,0 +1,147 @@
+/// Errors returned by the library, each one mapped to the
+/// exit code returned by the `pose` command.
+use crate::RegistryError;
+use std::fmt;
+
+#[derive(Debug, PartialEq)]
+pub enum PoseError {
+    /// a file doesn't exist, e.g. a compose file passed
+    NotFound(String),
+    /// the file couldn't be downloaded from any of the URLs
+    DownloadFailed,
+    /// wrong value in an argument, e.g. an invalid filter
+    InvalidArgument(String),
+    InvalidUrl(String),
+    /// the URL has no filename and no output file was passed
+    UrlWithoutFilename,
+    /// the server responded with an unexpected HTTP status
+    HttpStatus(String),
+    /// the downloaded file couldn't be created
+    CreateFile(String),
+    /// the downloaded file couldn't be written
+    WriteFile(String),
+    /// the server couldn't be reached, e.g. unknown host
+    HttpTransport(String),
+    /// the checksum of the downloaded file doesn't match the expected one
+    Checksum(String),
+    /// error fetching the image info from the remote registry
+    Registry(String, RegistryError),
+    /// unexpected errors, e.g. a process terminated by a signal
+    Other(String),
+    /// a file couldn't be read, e.g. the compose file or an env file
+    ReadFile(String),
+    Interpolation(String),
+    /// the YAML is valid, but not a compose file
+    InvalidCompose,
+    /// number of violations of the Compose specification found
+    InvalidSchema(usize),
+    ParseYaml(String),
+    /// a section is missing in the compose file, e.g. "services"
+    MissingSection(String),
+    ServiceNotFound(String),
+    Utf8(String),
+    /// the output couldn't be written, e.g. the `--output` file
+    WriteOutput(String),
+    Serialize(String),
+    /// git couldn't be called
+    Git(String),
+    /// path of services that form a cycle
+    CircularDependency(Vec<String>),
+    /// number of violations with error severity found by the linter
+    LintErrors(usize),
+    /// number of ports published in a host port already used
+    PortCollisions(usize),
+    /// a command like `docker` was executed but failed, with its exit code
+    Command(String, i32),
+    /// a command like `docker` couldn't be executed
+    CommandCall(String),
+}
+
+impl PoseError {
+    /// Exit code returned by the `pose` command for the error.
+    ///
+    /// ```
+    /// use docker_pose::PoseError;
+    ///
+    /// assert_eq!(PoseError::ServiceNotFound("app".to_string()).exit_code(), 16);
+    /// assert_eq!(PoseError::Command("calling compose".to_string(), 125).exit_code(), 125);
+    /// ```
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            PoseError::NotFound(_) | PoseError::DownloadFailed => 1,
+            PoseError::InvalidArgument(_) => 2,
+            PoseError::InvalidUrl(_) => 3,
+            PoseError::UrlWithoutFilename => 4,
+            PoseError::HttpStatus(_) | PoseError::CreateFile(_) => 5,
+            PoseError::WriteFile(_) => 6,
+            PoseError::HttpTransport(_) => 7,
+            PoseError::Registry(_, _) => 8,
+            PoseError::Other(_) => 10,
+            PoseError::ReadFile(_) => 11,
+            PoseError::Interpolation(_) => 12,
+            PoseError::InvalidCompose | PoseError::InvalidSchema(_) => 13,
+            PoseError::ParseYaml(_) | PoseError::MissingSection(_) => 15,
+            PoseError::ServiceNotFound(_) => 16,
+            PoseError::Utf8(_) => 17,
+            PoseError::WriteOutput(_) => 18,
+            PoseError::Serialize(_) => 20,
+            PoseError::Git(_) => 21,
+            PoseError::CircularDependency(_) => 22,
+            PoseError::LintErrors(_) => 23,
+            PoseError::PortCollisions(_) => 24,
+            PoseError::Checksum(_) => 25,
+            PoseError::Command(_, exit_code) => *exit_code,
+            PoseError::CommandCall(_) => 151,
+        }
+    }
+}
+
+impl fmt::Display for PoseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PoseError::NotFound(msg)
+            | PoseError::InvalidArgument(msg)
+            | PoseError::HttpStatus(msg)
+            | PoseError::CreateFile(msg)
+            | PoseError::WriteFile(msg)
+            | PoseError::HttpTransport(msg)
+            | PoseError::Checksum(msg)
+            | PoseError::Other(msg)
+            | PoseError::ReadFile(msg)
+            | PoseError::Interpolation(msg)
+            | PoseError::Utf8(msg)
+            | PoseError::WriteOutput(msg)
+            | PoseError::Serialize(msg)
+            | PoseError::Command(msg, _)
+            | PoseError::CommandCall(msg) => write!(f, "{}", msg),
+            PoseError::DownloadFailed => write!(f, "Download failed"),
+            PoseError::InvalidUrl(msg) => write!(f, "invalid URL - {}", msg),
+            PoseError::UrlWithoutFilename => write!(
+                f,
+                "URL without filename, you have to provide \
+                the filename where to store the file with the argument -o, --output"
+            ),
+            PoseError::Registry(image, e) => {
+                write!(f, "fetching image manifest for {}: {}", image, e)
+            }
+            PoseError::InvalidCompose => write!(f, "parsing compose YAML file: invalid content"),
+            PoseError::InvalidSchema(1) => write!(f, "1 error found validating the compose file"),
+            PoseError::InvalidSchema(count) => {
+                write!(f, "{} errors found validating the compose file", count)
+            }
+            PoseError::ParseYaml(msg) => write!(f, "parsing YAML file: {}", msg),
+            PoseError::MissingSection(section) => write!(f, "No {} section found", section),
+            PoseError::ServiceNotFound(service) => write!(f, "No such service found: {}", service),
+            PoseError::Git(msg) => write!(f, "calling git: {}", msg),
+            PoseError::CircularDependency(cycle) => {
+                write!(f, "circular dependency found: {}", cycle.join(" -> "))
+            }
+            PoseError::LintErrors(1) => write!(f, "1 lint error found"),
+            PoseError::LintErrors(count) => write!(f, "{} lint errors found", count),
+            PoseError::PortCollisions(1) => write!(f, "1 port collision found"),
+            PoseError::PortCollisions(count) => write!(f, "{} port collisions found", count),
+        }
+    }
+}
+
+impl std::error::Error for PoseError {}
@@ -0,0 +1,303 @@
+/// Dependency graph of the services, built from their `depends_on` attributes.
+use crate::{ComposeYaml, PoseError};
+use serde::Serialize;
+use serde_yaml::Value;
+use std::collections::{HashMap, HashSet};
+
+/// A dependency declared with `depends_on` from `service` to `depends_on`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Dependency {
+    pub service: String,
+    pub depends_on: String,
+    /// condition to wait for, "service_started" by default
+    pub condition: String,
+    /// whether the dependency is required to start the service, `true` by default
+    pub required: bool,
+}
+
+pub struct DependencyGraph {
+    services: Vec<String>,
+    dependencies: Vec<Dependency>,
+}
+
+impl DependencyGraph {
+    pub fn new(compose: &ComposeYaml) -> Self {
+        let services = compose
+            .get_root_element_names("services")
+            .into_iter()
+            .map(String::from)
+            .collect::<Vec<_>>();
+        let dependencies = services
+            .iter()
+            .flat_map(|name| {
+                compose
+                    .get_service(name)
+                    .map(|service| get_dependencies(name, service.get("depends_on")))
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>();
+        Self {
+            services,
+            dependencies,
+        }
+    }
+
+    pub fn get_services(&self) -> &[String] {
+        &self.services
+    }
+
+    pub fn get_dependencies(&self) -> &[Dependency] {
+        &self.dependencies
+    }
+
+    /// Dependencies of the service passed, only the ones
+    /// that point to services defined in the compose file.
+    fn get_defined_dependencies<'a>(&'a self, service: &'a str) -> impl Iterator<Item = &'a str> {
+        self.dependencies
+            .iter()
+            .filter(move |d| d.service == service && self.services.contains(&d.depends_on))
+            .map(|d| d.depends_on.as_str())
+    }
+
+    /// Get the order in which the services have to be started, so each service
+    /// is started after the services it depends on, or an error with the path of
+    /// services that form a cycle if there is a circular dependency, where the first
+    /// and the last service of the path are the same.
+    /// When more than one service can be started, the order from the
+    /// compose file is respected.
+    pub fn get_startup_order(&self) -> Result<Vec<String>, PoseError> {
+        if let Some(cycle) = self.find_cycle() {
+            return Err(PoseError::CircularDependency(cycle));
+        }
+        let mut pending = self
+            .services
+            .iter()
+            .map(|s| (s.as_str(), self.get_defined_dependencies(s).count()))
+            .collect::<Vec<_>>();
+        let mut order: Vec<String> = Vec::with_capacity(self.services.len());
+        while !pending.is_empty() {
+            let pos = pending.iter().position(|(_, deps)| *deps == 0).unwrap();
+            let (service, _) = pending.remove(pos);
+            for (dependant, deps) in pending.iter_mut() {
+                *deps -= self
+                    .get_defined_dependencies(dependant)
+                    .filter(|d| *d == service)
+                    .count();
+            }
+            order.push(service.to_string());
+        }
+        Ok(order)
+    }
+
+    /// Get the services the service passed depends on, or the services that
+    /// depend on it if `reverse` is `true`. With `recursive` the transitive
+    /// dependencies are included as well, and the services are returned without
+    /// duplicates and in the order they have to be started, otherwise
+    /// they are returned in the order they are declared.
+    /// If there is a circular dependency between the service and the related services
+    /// a `PoseError::CircularDependency` error is returned, cycles elsewhere are ignored.
+    pub fn get_related_services(
+        &self,
+        service: &str,
+        recursive: bool,
+        reverse: bool,
+    ) -> Result<Vec<String>, PoseError> {
+        let neighbors = |name: &str| {
+            self.dependencies
+                .iter()
+                .filter(|d| match reverse {
+                    true => d.depends_on == name,
+                    false => d.service == name,
+                })
+                .map(|d| match reverse {
+                    true => d.service.clone(),
+                    false => d.depends_on.clone(),
+                })
+                .collect::<Vec<_>>()
+        };
+        if !recursive {
+            // the first occurrence is kept, e.g. a service that
+            // depends on the same service more than once
+            let mut related = neighbors(service);
+            let mut seen = HashSet::new();
+            related.retain(|name| seen.insert(name.clone()));
+            return Ok(related);
+        }
+        let mut related: Vec<String> = Vec::new();
+        let mut pending = neighbors(service);
+        while let Some(name) = pending.pop() {
+            if name != service && !related.contains(&name) {
+                pending.extend(neighbors(&name));
+                related.push(name);
+            }
+        }
+        let mut closure = related.clone();
+        closure.push(service.to_string());
+        let order = self.subgraph(&closure).get_startup_order()?;
+        let mut sorted = order
+            .into_iter()
+            .filter(|s| related.contains(s))
+            .collect::<Vec<_>>();
+        // services not defined in the compose file are placed at the end
+        related.retain(|s| !sorted.contains(s));
+        sorted.append(&mut related);
+        Ok(sorted)
+    }
+
+    /// Graph with only the services passed and the dependencies between them.
+    fn subgraph(&self, services: &[String]) -> Self {
+        Self {
+            services: self
+                .services
+                .iter()
+                .filter(|s| services.contains(s))
+                .cloned()
+                .collect(),
+            dependencies: self
+                .dependencies
+                .iter()
+                .filter(|d| services.contains(&d.service) && services.contains(&d.depends_on))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Find a circular dependency, returning the path of services
+    /// that form the cycle, e.g. `["app", "worker", "app"]`.
+    pub fn find_cycle(&self) -> Option<Vec<String>> {
+        // 1 = visiting, 2 = visited
+        let mut state: HashMap<&str, u8> = HashMap::new();
+        let mut path: Vec<&str> = Vec::new();
+        for service in &self.services {
+            if let Some(cycle) = self.visit(service, &mut state, &mut path) {
+                return Some(cycle);
+            }
+        }
+        None
+    }
+
+    fn visit<'a>(
+        &'a self,
+        service: &'a str,
+        state: &mut HashMap<&'a str, u8>,
+        path: &mut Vec<&'a str>,
+    ) -> Option<Vec<String>> {
+        match state.get(service) {
+            Some(2) => return None,
+            Some(_) => {
+                let start = path.iter().position(|s| *s == service).unwrap();
+                let mut cycle = path[start..]
+                    .iter()
+                    .map(|s| s.to_string())
+                    .collect::<Vec<_>>();
+                cycle.push(service.to_string());
+                return Some(cycle);
+            }
+            None => {}
+        }
+        state.insert(service, 1);
+        path.push(service);
+        for dep in self.get_defined_dependencies(service) {
+            if let Some(cycle) = self.visit(dep, state, path) {
+                return Some(cycle);
+            }
+        }
+        path.pop();
+        state.insert(service, 2);
+        None
+    }
+
+    /// Export the graph in the Graphviz DOT format. Dependencies with
+    /// a condition other than "service_started" are labeled with it,
+    /// and the ones not required are drawn with a dashed line.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph services {\n");
+        for service in &self.services {
+            dot.push_str(&format!("  \"{}\";\n", service));
+        }
+        for dep in &self.dependencies {
+            let mut attrs = Vec::new();
+            if dep.condition != "service_started" {
+                attrs.push(format!("label=\"{}\"", dep.condition));
+            }
+            if !dep.required {
+                attrs.push("style=dashed".to_string());
+            }
+            let attrs = match attrs.is_empty() {
+                true => String::new(),
+                false => format!(" [{}]", attrs.join(", ")),
+            };
+            dot.push_str(&format!(
+                "  \"{}\" -> \"{}\"{};\n",
+                dep.service, dep.depends_on, attrs
+            ));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Export the graph as a Mermaid flowchart. Dependencies with
+    /// a condition other than "service_started" are labeled with it,
+    /// and the ones not required are drawn with a dotted line.
+    pub fn to_mermaid(&self) -> String {
+        let mut mermaid = String::from("flowchart TD\n");
+        for service in &self.services {
+            mermaid.push_str(&format!("  {}[\"{}\"]\n", mermaid_id(service), service));
+        }
+        for dep in &self.dependencies {
+            let arrow = match dep.required {
+                true => "-->",
+                false => "-.->",
+            };
+            let label = match dep.condition.as_str() {
+                "service_started" => String::new(),
+                condition => format!("|{}|", condition),
+            };
+            mermaid.push_str(&format!(
+                "  {} {}{} {}\n",
+                mermaid_id(&dep.service),
+                arrow,
+                label,
+                mermaid_id(&dep.depends_on)
+            ));
+        }
+        mermaid
+    }
+}
+
+/// Parse the short and the long syntax of `depends_on`.
+fn get_dependencies(service: &str, depends_on: Option<&Value>) -> Vec<Dependency> {
+    let dependency = |name: &str, condition: Option<&str>, required: Option<bool>| Dependency {
+        service: service.to_string(),
+        depends_on: name.to_string(),
+        condition: condition.unwrap_or("service_started").to_string(),
+        required: required.unwrap_or(true),
+    };
+    match depends_on {
+        Some(Value::Sequence(seq)) => seq
+            .iter()
+            .flat_map(|el| el.as_str())
+            .filter(|name| !name.is_empty())
+            .map(|name| dependency(name, None, None))
+            .collect::<Vec<_>>(),
+        Some(Value::Mapping(map)) => map
+            .iter()
+            .flat_map(|(k, v)| {
+                let name = k.as_str()?;
+                let condition = v.get("condition").and_then(|c| c.as_str());
+                let required = v.get("required").and_then(|r| r.as_bool());
+                Some(dependency(name, condition, required))
+            })
+            .collect::<Vec<_>>(),
+        _ => Vec::default(),
+    }
+}
+
+/// Mermaid node identifiers can't have some symbols used in the
+/// services names, so the name is used as label of the node instead.
+fn mermaid_id(service: &str) -> String {
+    service
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
@@ -0,0 +1,179 @@
+/// Parser of image references like `registry:5000/namespace/app:tag@sha256:...`.
+use std::fmt;
+use std::str::FromStr;
+
+/// An image reference, parsed following the same rules the docker CLI uses:
+/// the first component of the name is a registry only if it has a `.`
+/// or a `:` (a port), or if it is `localhost`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImageRef {
+    /// registry host, without the port, e.g. `registry.example.com`
+    pub registry: Option<String>,
+    /// port of the registry
+    pub port: Option<u16>,
+    /// repository path, e.g. `namespace/app`
+    pub repository: String,
+    pub tag: Option<String>,
+    /// digest, e.g. `sha256:0123...`
+    pub digest: Option<String>,
+}
+
+impl ImageRef {
+    /// Parse an image reference.
+    ///
+    /// ```
+    /// use docker_pose::ImageRef;
+    ///
+    /// let image = ImageRef::parse("registry:5000/team/app:1.0").unwrap();
+    /// assert_eq!(image.registry, Some("registry".to_string()));
+    /// assert_eq!(image.port, Some(5000));
+    /// assert_eq!(image.repository, "team/app");
+    /// assert_eq!(image.tag, Some("1.0".to_string()));
+    /// assert_eq!(image.digest, None);
+    ///
+    /// let image = ImageRef::parse("mrsarm/mongotail").unwrap();
+    /// assert_eq!(image.registry, None);
+    /// assert_eq!(image.repository, "mrsarm/mongotail");
+    /// assert_eq!(image.tag, None);
+    ///
+    /// assert!(ImageRef::parse("app:${TAG:-latest}").is_err());
+    /// ```
+    pub fn parse(image: &str) -> Result<Self, String> {
+        let invalid =
+            |reason: &str| Err(format!("invalid image reference '{}': {}", image, reason));
+        if image.is_empty() || image.contains(char::is_whitespace) {
+            return invalid("empty or with white spaces");
+        }
+        let (name, digest) = match image.split_once('@') {
+            Some((name, digest)) => {
+                if !digest.contains(':') || digest.ends_with(':') {
+                    return invalid("wrong digest format");
+                }
+                (name, Some(digest.to_string()))
+            }
+            None => (image, None),
+        };
+        let (registry, port, remainder) = match name.split_once('/') {
+            Some((first, rest))
+                if first.contains('.') || first.contains(':') || first == "localhost" =>
+            {
+                let (host, port) = match first.rsplit_once(':') {
+                    Some((host, port)) if !port.contains(']') => match port.parse::<u16>() {
+                        Ok(p) => (host, Some(p)),
+                        Err(_) => return invalid("wrong registry port"),
+                    },
+                    _ => (first, None),
+                };
+                if host.is_empty() {
+                    return invalid("empty registry host");
+                }
+                (Some(host.to_string()), port, rest)
+            }
+            _ => (None, None, name),
+        };
+        let (repository, tag) = match remainder.rsplit_once(':') {
+            Some((repository, tag)) => {
+                if tag.is_empty() || tag.contains(['/', '$', '{', '}']) {
+                    return invalid("wrong tag format");
+                }
+                (repository, Some(tag.to_string()))
+            }
+            None => (remainder, None),
+        };
+        if repository.is_empty()
+            || repository.contains([':', '$', '{', '}'])
+            || repository.split('/').any(|p| p.is_empty())
+        {
+            return invalid("wrong repository name");
+        }
+        Ok(Self {
+            registry,
+            port,
+            repository: repository.to_string(),
+            tag,
+            digest,
+        })
+    }
+
+    /// Name of the image without the tag and the digest,
+    /// including the registry and its port if present.
+    ///
+    /// ```
+    /// use docker_pose::ImageRef;
+    ///
+    /// let image = ImageRef::parse("localhost:5000/app:1.0@sha256:abc").unwrap();
+    /// assert_eq!(image.name(), "localhost:5000/app");
+    /// ```
+    pub fn name(&self) -> String {
+        match (&self.registry, self.port) {
+            (Some(registry), Some(port)) => format!("{}:{}/{}", registry, port, self.repository),
+            (Some(registry), None) => format!("{}/{}", registry, self.repository),
+            _ => self.repository.clone(),
+        }
+    }
+
+    /// Name of the image including the default registry `docker.io`
+    /// and namespace `library` when they are omitted.
+    ///
+    /// ```
+    /// use docker_pose::ImageRef;
+    ///
+    /// let image = ImageRef::parse("postgres:15").unwrap();
+    /// assert_eq!(image.canonical_name(), "docker.io/library/postgres");
+    /// let image = ImageRef::parse("localhost:5000/app").unwrap();
+    /// assert_eq!(image.canonical_name(), "localhost:5000/app");
+    /// ```
+    pub fn canonical_name(&self) -> String {
+        match (&self.registry, self.repository.contains('/')) {
+            (Some(_), _) => self.name(),
+            (None, true) => format!("docker.io/{}", self.repository),
+            (None, false) => format!("docker.io/library/{}", self.repository),
+        }
+    }
+
+    /// Tag of the image, `latest` if the image doesn't have a tag or a digest.
+    pub fn get_tag(&self) -> &str {
+        match (&self.tag, &self.digest) {
+            (Some(tag), _) => tag,
+            (None, Some(_)) => "",
+            (None, None) => "latest",
+        }
+    }
+
+    /// Get a new reference of the same image with the tag passed, without digest.
+    ///
+    /// ```
+    /// use docker_pose::ImageRef;
+    ///
+    /// let image = ImageRef::parse("registry:5000/app:1.0@sha256:abc").unwrap();
+    /// assert_eq!(image.with_tag("2.0").to_string(), "registry:5000/app:2.0");
+    /// ```
+    pub fn with_tag(&self, tag: &str) -> Self {
+        Self {
+            tag: Some(tag.to_string()),
+            digest: None,
+            ..self.clone()
+        }
+    }
+}
+
+impl FromStr for ImageRef {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for ImageRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())?;
+        if let Some(tag) = &self.tag {
+            write!(f, ":{}", tag)?;
+        }
+        if let Some(digest) = &self.digest {
+            write!(f, "@{}", digest)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,245 @@
+/// Interpolation of variables in compose files, following the syntax
+/// of the Compose specification:
+/// https://github.com/compose-spec/compose-spec/blob/master/12-interpolation.md
+use crate::{read_dotenv, PoseError, Verbosity};
+use colored::Colorize;
+use serde_yaml::Value;
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap};
+use std::env;
+use std::path::{Path, PathBuf};
+
+pub struct Interpolator {
+    vars: HashMap<String, String>,
+    verbosity: Verbosity,
+    /// variables not set already reported, to warn only once about each of them
+    warned: RefCell<BTreeSet<String>>,
+}
+
+impl Interpolator {
+    pub fn new(vars: HashMap<String, String>, verbosity: Verbosity) -> Self {
+        Self {
+            vars,
+            verbosity,
+            warned: RefCell::new(BTreeSet::new()),
+        }
+    }
+
+    /// Create an interpolator with the variables from the `env_files` passed, or
+    /// from the `.env` file found in the project directory (if exists) when no files
+    /// are passed, and the variables from the environment, that take precedence
+    /// over the ones from the files.
+    pub fn from_env(
+        project_dir: &Path,
+        env_files: &[String],
+        verbosity: Verbosity,
+    ) -> Result<Self, PoseError> {
+        let mut paths = env_files.iter().map(PathBuf::from).collect::<Vec<_>>();
+        if paths.is_empty() {
+            let dotenv_path = project_dir.join(".env");
+            if dotenv_path.is_file() {
+                paths.push(dotenv_path);
+            }
+        }
+        let mut vars = HashMap::new();
+        for path in paths {
+            if matches!(verbosity, Verbosity::Verbose) {
+                eprintln!(
+                    "{}: Loading variables from {}",
+                    "DEBUG".green(),
+                    path.display()
+                );
+            }
+            for (key, value) in read_dotenv(&path).map_err(PoseError::Interpolation)? {
+                if let Some(val) = value {
+                    vars.insert(key, val);
+                }
+            }
+        }
+        vars.extend(env::vars());
+        Ok(Self::new(vars, verbosity))
+    }
+
+    /// Interpolate the variables found in the text.
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use docker_pose::{Interpolator, Verbosity};
+    ///
+    /// let vars = HashMap::from([
+    ///     ("TAG".to_string(), "1.0".to_string()),
+    ///     ("EMPTY".to_string(), "".to_string()),
+    /// ]);
+    /// let interpolator = Interpolator::new(vars, Verbosity::Quiet);
+    ///
+    /// assert_eq!(interpolator.interpolate("app:$TAG"), Ok("app:1.0".to_string()));
+    /// assert_eq!(interpolator.interpolate("app:${TAG}"), Ok("app:1.0".to_string()));
+    /// assert_eq!(interpolator.interpolate("${EMPTY:-latest}"), Ok("latest".to_string()));
+    /// assert_eq!(interpolator.interpolate("${EMPTY-latest}"), Ok("".to_string()));
+    /// assert_eq!(interpolator.interpolate("${NOPE:-${TAG:-x}}"), Ok("1.0".to_string()));
+    /// assert_eq!(interpolator.interpolate("${TAG:+set}"), Ok("set".to_string()));
+    /// assert_eq!(interpolator.interpolate("$$TAG"), Ok("$TAG".to_string()));
+    /// assert_eq!(
+    ///     interpolator.interpolate("${NOPE:?tag is required}"),
+    ///     Err("required variable NOPE is missing a value: tag is required".to_string())
+    /// );
+    /// assert_eq!(
+    ///     interpolator.interpolate("app:${TAG"),
+    ///     Err("invalid interpolation format for \"app:${TAG\"".to_string())
+    /// );
+    /// ```
+    pub fn interpolate(&self, text: &str) -> Result<String, String> {
+        let mut result = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(pos) = rest.find('$') {
+            result.push_str(&rest[..pos]);
+            let after = &rest[pos + 1..];
+            if let Some(after) = after.strip_prefix('$') {
+                // "$$" is an escaped "$"
+                result.push('$');
+                rest = after;
+            } else if let Some(expr) = after.strip_prefix('{') {
+                let end = closing_brace(expr)
+                    .ok_or_else(|| format!("invalid interpolation format for \"{}\"", text))?;
+                result.push_str(&self.eval(&expr[..end], text)?);
+                rest = &expr[end + 1..];
+            } else {
+                let name_len = var_name_len(after);
+                if name_len > 0 {
+                    result.push_str(&self.lookup(&after[..name_len]));
+                } else {
+                    result.push('$');
+                }
+                rest = &after[name_len..];
+            }
+        }
+        result.push_str(rest);
+        Ok(result)
+    }
+
+    /// Interpolate all the strings found in the value recursively, `path` is
+    /// the path of the value in the document used to report errors,
+    /// e.g. "services.app.image".
+    pub fn interpolate_value(&self, value: &mut Value, path: &str) -> Result<(), String> {
+        match value {
+            Value::String(string) => {
+                *string = self
+                    .interpolate(string)
+                    .map_err(|e| format!("error while interpolating {}: {}", path, e))?;
+            }
+            Value::Sequence(seq) => {
+                for (i, el) in seq.iter_mut().enumerate() {
+                    self.interpolate_value(el, &format!("{}[{}]", path, i))?;
+                }
+            }
+            Value::Mapping(map) => {
+                for (key, val) in map.iter_mut() {
+                    let key_path = match key.as_str() {
+                        Some(k) if path.is_empty() => k.to_string(),
+                        Some(k) => format!("{}.{}", path, k),
+                        None => path.to_string(),
+                    };
+                    self.interpolate_value(val, &key_path)?;
+                }
+            }
+            Value::Tagged(tagged) => self.interpolate_value(&mut tagged.value, path)?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Evaluate an expression found between `${` and `}`.
+    fn eval(&self, expr: &str, text: &str) -> Result<String, String> {
+        let name_len = var_name_len(expr);
+        if name_len == 0 {
+            return Err(format!("invalid interpolation format for \"{}\"", text));
+        }
+        let name = &expr[..name_len];
+        let value = self.vars.get(name);
+        let op = &expr[name_len..];
+        let (op, arg) = match op
+            .char_indices()
+            .find(|(_, c)| matches!(c, '-' | '?' | '+'))
+        {
+            Some((i, c)) => (&op[..i + c.len_utf8()], &op[i + c.len_utf8()..]),
+            None => (op, ""),
+        };
+        let unset_or_empty = value.map(|v| v.is_empty()).unwrap_or(true);
+        match op {
+            "" => Ok(self.lookup(name)),
+            ":-" if unset_or_empty => self.interpolate(arg),
+            "-" if value.is_none() => self.interpolate(arg),
+            ":-" | "-" => Ok(value.cloned().unwrap_or_default()),
+            ":?" | "?" if (op == ":?" && unset_or_empty) || value.is_none() => {
+                let msg = self.interpolate(arg)?;
+                match msg.is_empty() {
+                    true => Err(format!("required variable {} is missing a value", name)),
+                    false => Err(format!(
+                        "required variable {} is missing a value: {}",
+                        name, msg
+                    )),
+                }
+            }
+            ":?" | "?" => Ok(value.cloned().unwrap_or_default()),
+            ":+" if !unset_or_empty => self.interpolate(arg),
+            "+" if value.is_some() => self.interpolate(arg),
+            ":+" | "+" => Ok(String::new()),
+            _ => Err(format!("invalid interpolation format for \"{}\"", text)),
+        }
+    }
+
+    /// Get the value of the variable, or an empty string if
+    /// it's not set, warning about it.
+    fn lookup(&self, name: &str) -> String {
+        match self.vars.get(name) {
+            Some(val) => val.to_string(),
+            None => {
+                if !matches!(self.verbosity, Verbosity::Quiet)
+                    && self.warned.borrow_mut().insert(name.to_string())
+                {
+                    eprintln!(
+                        "{}: The \"{}\" variable is not set. Defaulting to a blank string.",
+                        "WARN".yellow(),
+                        name
+                    );
+                }
+                String::new()
+            }
+        }
+    }
+}
+
+/// Length of the variable name at the beginning of the text,
+/// or 0 if the text doesn't start with a valid name.
+fn var_name_len(text: &str) -> usize {
+    if !text.starts_with(|c: char| c == '_' || c.is_ascii_alphabetic()) {
+        return 0;
+    }
+    text.find(|c: char| c != '_' && !c.is_ascii_alphanumeric())
+        .unwrap_or(text.len())
+}
+
+/// Position of the `}` that closes the expression, taking
+/// into account nested `${...}` expressions.
+fn closing_brace(expr: &str) -> Option<usize> {
+    let mut depth = 1;
+    let bytes = expr.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'$' if bytes.get(i + 1) == Some(&b'{') => {
+                depth += 1;
+                i += 1;
+            }
+            b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
@@ -2,27 +2,68 @@
 extern crate lazy_static;
 
 mod args;
+mod cache;
 mod cmd;
+mod diff;
+mod digest;
 mod docker;
+mod dotenv;
+mod error;
 mod git;
+mod graph;
 mod http;
+mod image;
+mod interpolate;
+mod lint;
+mod merge;
+mod mounts;
+mod netrc;
 mod parse;
+mod ports;
+mod proxy;
+mod registry;
+mod schema;
 mod utils;
 mod verbose;
 
-pub use args::{Args, Commands, Formats, Objects};
+pub use args::{Args, Commands, DiffObjects, Formats, GraphFormats, LintFormats, Objects};
+pub use cache::{DownloadCache, TagCache};
 pub use cmd::{
-    cmd_call, cmd_call_to_string, cmd_exit_code, cmd_get_success_output_or_fail, cmd_write_stderr,
+    cmd_call, cmd_call_to_string, cmd_exit_code, cmd_get_success_output, cmd_write_stderr,
     cmd_write_stdout,
 };
+pub use diff::{diff_envs, is_secret_name, Change, EnvDiff, MASK};
+pub use digest::get_repo_digest;
 pub use docker::DockerCommand;
+pub use dotenv::{parse_dotenv, read_dotenv};
+pub use error::PoseError;
 pub use git::GitCommand;
-pub use http::get_and_save;
+pub use graph::{Dependency, DependencyGraph};
+pub use http::{
+    fill_url_template, get_and_save, get_urls, has_url_placeholders, is_sha256, mask_header,
+    mask_url, parse_checksum, Credentials, Fallback, GetOptions, URL_PLACEHOLDERS,
+};
+pub use image::ImageRef;
+pub use interpolate::Interpolator;
+pub use lint::{
+    locate_violations, to_sarif, FileLintConfig, ImageTagRule, LintConfig, Linter,
+    PortCollisionRule, PrivilegedRule, Rule, Severity, UndefinedResourceRule, UndefinedServiceRule,
+    Violation, LINT_CONFIG_FILE,
+};
+pub use mounts::{find_mount_issues, Mount, MountType, ServiceMount};
+pub use netrc::{Netrc, NetrcEntry};
 pub use parse::{
-    get_compose_filename, header, positive_less_than_32, string_no_empty, string_script,
-    ComposeYaml, ReplaceTag,
+    fallback, get_compose_filename, header, positive_less_than_32, sha256, string_no_empty,
+    string_script, ComposeYaml, EnvVar, PinDigest, ProfileServices, ReplaceTag, ServiceImage,
+};
+pub use ports::{find_port_collisions, PortMapping, PortRange, ServicePort};
+pub use proxy::{is_no_proxy, parse_no_proxy, ProxyConfig};
+pub use registry::{
+    read_docker_auths, read_docker_insecure_registries, RegistryClient, RegistryError,
 };
+pub use schema::{find_line, validate_compose_file, PathSegment, SchemaError, SchemaValidator};
 pub use utils::{
-    get_service, get_slug, get_yml_content, print_names, unwrap_filter_regex, unwrap_filter_tag,
+    get_cache_dir, get_project_dir, get_service, get_slug, get_yml_content, print_names,
+    print_records, print_table, unwrap_filter_regex, unwrap_filter_tag,
 };
 pub use verbose::Verbosity;
@@ -0,0 +1,530 @@
+/// Rules to check best practices in the compose model, e.g. images
+/// using the `latest` tag or references to resources not defined.
+use crate::schema::find_path;
+use crate::{find_port_collisions, ComposeYaml, PathSegment, PoseError};
+use clap::crate_version;
+use serde::Deserialize;
+use serde_json::json;
+use serde_yaml::{Mapping, Value};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// Config file of the linter, looked up in the project directory.
+pub const LINT_CONFIG_FILE: &str = ".pose-lint.yaml";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, strum_macros::Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+/// A problem found by a rule.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Violation {
+    pub rule_id: String,
+    pub severity: Severity,
+    pub message: String,
+    /// path of the value with the problem, e.g. `services.app.image`
+    pub path: Vec<PathSegment>,
+    /// compose file and line where the value is defined, if found
+    pub file: Option<String>,
+    pub line: Option<usize>,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} [{}] {}", self.severity, self.rule_id, self.message)
+    }
+}
+
+/// A check over the compose model. New rules can be
+/// added to the linter implementing this trait.
+pub trait Rule {
+    /// Unique identifier of the rule, used to disable it.
+    fn id(&self) -> &'static str;
+    fn description(&self) -> &'static str;
+    fn severity(&self) -> Severity;
+    fn check(&self, compose: &ComposeYaml) -> Vec<Violation>;
+
+    fn violation(&self, path: Vec<PathSegment>, message: String) -> Violation {
+        Violation {
+            rule_id: self.id().to_string(),
+            severity: self.severity(),
+            message,
+            path,
+            file: None,
+            line: None,
+        }
+    }
+}
+
+pub struct Linter {
+    rules: Vec<Box<dyn Rule>>,
+}
+
+impl Default for Linter {
+    /// Linter with all the rules provided by pose.
+    fn default() -> Self {
+        Self::new(vec![
+            Box::new(ImageTagRule),
+            Box::new(PrivilegedRule),
+            Box::new(PortCollisionRule),
+            Box::new(UndefinedServiceRule),
+            Box::new(UndefinedResourceRule::new("volume", "volumes")),
+            Box::new(UndefinedResourceRule::new("network", "networks")),
+            Box::new(UndefinedResourceRule::new("secret", "secrets")),
+            Box::new(UndefinedResourceRule::new("config", "configs")),
+        ])
+    }
+}
+
+impl Linter {
+    pub fn new(rules: Vec<Box<dyn Rule>>) -> Self {
+        Self { rules }
+    }
+
+    pub fn add_rule(&mut self, rule: Box<dyn Rule>) {
+        self.rules.push(rule);
+    }
+
+    pub fn get_rules(&self) -> &[Box<dyn Rule>] {
+        &self.rules
+    }
+
+    /// Run all the rules not disabled for all the files in the config.
+    pub fn lint(&self, compose: &ComposeYaml, config: &LintConfig) -> Vec<Violation> {
+        self.rules
+            .iter()
+            .filter(|rule| !config.disable.iter().any(|id| id == rule.id()))
+            .flat_map(|rule| rule.check(compose))
+            .collect::<Vec<_>>()
+    }
+}
+
+/// Rules disabled, for all the compose files or only for some of them.
+///
+/// ```yaml
+/// disable:
+///   - image-tag
+/// files:
+///   compose.dev.yaml:
+///     disable:
+///       - privileged
+/// ```
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LintConfig {
+    #[serde(default)]
+    pub disable: Vec<String>,
+    /// config by compose filename
+    #[serde(default)]
+    pub files: HashMap<String, FileLintConfig>,
+}
+
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FileLintConfig {
+    #[serde(default)]
+    pub disable: Vec<String>,
+}
+
+impl LintConfig {
+    pub fn load(path: &Path) -> Result<Self, PoseError> {
+        let content = fs::read_to_string(path).map_err(|e| {
+            PoseError::ReadFile(format!("reading lint config '{}': {}", path.display(), e))
+        })?;
+        Self::parse(&content)
+            .map_err(|e| PoseError::ParseYaml(format!("lint config '{}': {}", path.display(), e)))
+    }
+
+    pub fn parse(content: &str) -> Result<Self, serde_yaml::Error> {
+        match content.trim().is_empty() {
+            true => Ok(Self::default()),
+            false => serde_yaml::from_str(content),
+        }
+    }
+
+    /// Whether the rule is disabled for all the files, or for
+    /// the compose file passed.
+    ///
+    /// ```
+    /// use docker_pose::LintConfig;
+    ///
+    /// let config = LintConfig::parse("
+    /// disable: [image-tag]
+    /// files:
+    ///   compose.dev.yaml:
+    ///     disable: [privileged]
+    /// ").unwrap();
+    /// assert!(config.is_disabled("image-tag", None));
+    /// assert!(config.is_disabled("privileged", Some("dev/compose.dev.yaml")));
+    /// assert!(!config.is_disabled("privileged", Some("compose.yaml")));
+    /// ```
+    pub fn is_disabled(&self, rule_id: &str, file: Option<&str>) -> bool {
+        self.disable.iter().any(|id| id == rule_id)
+            || file
+                .map(|file| {
+                    self.files.iter().any(|(name, config)| {
+                        Path::new(file).ends_with(name)
+                            && config.disable.iter().any(|id| id == rule_id)
+                    })
+                })
+                .unwrap_or(false)
+    }
+
+    /// IDs of the rules in the config that don't exist in the linter.
+    pub fn get_unknown_rules(&self, linter: &Linter) -> Vec<&str> {
+        self.disable
+            .iter()
+            .chain(self.files.values().flat_map(|f| f.disable.iter()))
+            .map(|id| id.as_str())
+            .filter(|id| !linter.get_rules().iter().any(|rule| rule.id() == *id))
+            .collect::<Vec<_>>()
+    }
+}
+
+/// Set the file and the line where the value of each violation is defined,
+/// looking for the value in the compose files passed as `(filename, content)`.
+/// If the value is defined in more than one file, the last one is used,
+/// as the last file overrides the previous ones.
+pub fn locate_violations(violations: &mut [Violation], files: &[(String, String)]) {
+    for violation in violations.iter_mut() {
+        let location = files
+            .iter()
+            .flat_map(|(filename, content)| {
+                find_path(content, &violation.path).map(|(line, depth)| (filename, line, depth))
+            })
+            .max_by_key(|(_, _, depth)| *depth);
+        if let Some((filename, line, _)) = location {
+            violation.file = Some(filename.to_string());
+            violation.line = Some(line);
+        }
+    }
+}
+
+/// Report of the violations in the SARIF format, used by code
+/// review tools to annotate the lines with problems.
+pub fn to_sarif(linter: &Linter, violations: &[Violation]) -> serde_json::Value {
+    let level = |severity: Severity| match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Info => "note",
+    };
+    let rules = linter
+        .get_rules()
+        .iter()
+        .map(|rule| {
+            json!({
+                "id": rule.id(),
+                "shortDescription": {"text": rule.description()},
+                "defaultConfiguration": {"level": level(rule.severity())},
+            })
+        })
+        .collect::<Vec<_>>();
+    let results = violations
+        .iter()
+        .map(|v| {
+            let mut result = json!({
+                "ruleId": v.rule_id,
+                "level": level(v.severity),
+                "message": {"text": v.message},
+            });
+            if let Some(file) = &v.file {
+                let mut location = json!({"artifactLocation": {"uri": file}});
+                if let Some(line) = v.line {
+                    location["region"] = json!({"startLine": line});
+                }
+                result["locations"] = json!([{"physicalLocation": location}]);
+            }
+            result
+        })
+        .collect::<Vec<_>>();
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "pose",
+                    "version": crate_version!(),
+                    "informationUri": "https://github.com/mrsarm/pose",
+                    "rules": rules,
+                }
+            },
+            "results": results,
+        }],
+    })
+}
+
+fn key(name: &str) -> PathSegment {
+    PathSegment::Key(name.to_string())
+}
+
+/// Iterate the services that are defined as a mapping.
+fn services(compose: &ComposeYaml) -> impl Iterator<Item = (&str, &Mapping)> {
+    compose
+        .get_services()
+        .into_iter()
+        .flatten()
+        .flat_map(|(name, service)| Some((name.as_str()?, service.as_mapping()?)))
+}
+
+/// Path of the reference to `name` in the attribute of the service, e.g. the
+/// item `db:/var/lib/data` of the `volumes` attribute for the volume `db`.
+fn reference_path(service: &str, attribute: &str, value: &Value, name: &str) -> Vec<PathSegment> {
+    let mut path = vec![key("services"), key(service), key(attribute)];
+    match value {
+        Value::Sequence(seq) => {
+            let index = seq.iter().position(|el| match el {
+                Value::String(s) => s == name || s.starts_with(&format!("{}:", name)),
+                Value::Mapping(map) => map.get("source").and_then(|s| s.as_str()) == Some(name),
+                _ => false,
+            });
+            if let Some(index) = index {
+                path.push(PathSegment::Index(index));
+            }
+        }
+        Value::Mapping(map) if map.contains_key(name) => path.push(key(name)),
+        _ => {}
+    }
+    path
+}
+
+/// Images without a tag or with the `latest` tag,
+/// the image used may change without notice.
+pub struct ImageTagRule;
+
+impl Rule for ImageTagRule {
+    fn id(&self) -> &'static str {
+        "image-tag"
+    }
+
+    fn description(&self) -> &'static str {
+        "Images should have a tag other than \"latest\""
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn check(&self, compose: &ComposeYaml) -> Vec<Violation> {
+        services(compose)
+            .flat_map(|(name, service)| {
+                let image = service.get("image")?.as_str()?;
+                // images with variables not interpolated are skipped
+                let image_ref = crate::ImageRef::parse(image).ok()?;
+                if image_ref.digest.is_some() {
+                    return None;
+                }
+                let message = match image_ref.tag.as_deref() {
+                    None => format!(
+                        "service '{}' uses the image '{}' without a tag",
+                        name, image
+                    ),
+                    Some("latest") => {
+                        format!(
+                            "service '{}' uses the image '{}' with the latest tag",
+                            name, image
+                        )
+                    }
+                    Some(_) => return None,
+                };
+                Some(self.violation(vec![key("services"), key(name), key("image")], message))
+            })
+            .collect::<Vec<_>>()
+    }
+}
+
+/// Services with `privileged: true`, that have full access to the host.
+pub struct PrivilegedRule;
+
+impl Rule for PrivilegedRule {
+    fn id(&self) -> &'static str {
+        "privileged"
+    }
+
+    fn description(&self) -> &'static str {
+        "Services should not run in privileged mode"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn check(&self, compose: &ComposeYaml) -> Vec<Violation> {
+        services(compose)
+            .filter(|(_, service)| match service.get("privileged") {
+                Some(Value::Bool(privileged)) => *privileged,
+                Some(Value::String(privileged)) => privileged == "true",
+                _ => false,
+            })
+            .map(|(name, _)| {
+                self.violation(
+                    vec![key("services"), key(name), key("privileged")],
+                    format!("service '{}' runs in privileged mode", name),
+                )
+            })
+            .collect::<Vec<_>>()
+    }
+}
+
+/// Ports published in the same port of the host by different services,
+/// or more than once by the same service.
+pub struct PortCollisionRule;
+
+impl Rule for PortCollisionRule {
+    fn id(&self) -> &'static str {
+        "port-collision"
+    }
+
+    fn description(&self) -> &'static str {
+        "Host ports should be published only once"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn check(&self, compose: &ComposeYaml) -> Vec<Violation> {
+        let ports = compose.get_ports();
+        find_port_collisions(&ports)
+            .into_iter()
+            .map(|(port, other)| {
+                self.violation(
+                    vec![
+                        key("services"),
+                        key(&port.service),
+                        key("ports"),
+                        PathSegment::Index(port.index),
+                    ],
+                    format!(
+                        "port {} of service '{}' is already published by service '{}' ({})",
+                        port.port, port.service, other.service, other.port
+                    ),
+                )
+            })
+            .collect::<Vec<_>>()
+    }
+}
+
+/// Services in `depends_on` that are not defined.
+pub struct UndefinedServiceRule;
+
+impl Rule for UndefinedServiceRule {
+    fn id(&self) -> &'static str {
+        "undefined-service"
+    }
+
+    fn description(&self) -> &'static str {
+        "Services in depends_on should be defined"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn check(&self, compose: &ComposeYaml) -> Vec<Violation> {
+        let defined = compose.get_root_element_names("services");
+        services(compose)
+            .flat_map(|(name, service)| {
+                compose
+                    .get_service_depends_on(service)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|dep| !defined.contains(&dep.as_str()))
+                    .map(|dep| {
+                        let depends_on = service.get("depends_on").unwrap_or(&Value::Null);
+                        self.violation(
+                            reference_path(name, "depends_on", depends_on, &dep),
+                            format!("service '{}' depends on undefined service '{}'", name, dep),
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>()
+    }
+}
+
+/// Named volumes, networks, secrets or configs used by the
+/// services that are not defined at the top level of the file.
+pub struct UndefinedResourceRule {
+    id: &'static str,
+    description: &'static str,
+    /// name of the resource, e.g. "volume"
+    resource: &'static str,
+    /// attribute of the services and top-level element, e.g. "volumes"
+    element: &'static str,
+}
+
+impl UndefinedResourceRule {
+    fn new(resource: &'static str, element: &'static str) -> Self {
+        let (id, description) = match resource {
+            "volume" => ("undefined-volume", "Named volumes should be defined"),
+            "network" => ("undefined-network", "Networks should be defined"),
+            "secret" => ("undefined-secret", "Secrets should be defined"),
+            _ => ("undefined-config", "Configs should be defined"),
+        };
+        Self {
+            id,
+            description,
+            resource,
+            element,
+        }
+    }
+
+    fn get_references(&self, compose: &ComposeYaml, service: &Mapping) -> Vec<String> {
+        match self.element {
+            "volumes" => compose.get_service_named_volumes(service),
+            // the default network is created by compose
+            "networks" => compose
+                .get_service_networks(service)
+                .into_iter()
+                .filter(|n| n != "default")
+                .collect(),
+            "secrets" => compose.get_service_secrets(service),
+            _ => compose.get_service_configs(service),
+        }
+    }
+}
+
+impl Rule for UndefinedResourceRule {
+    fn id(&self) -> &'static str {
+        self.id
+    }
+
+    fn description(&self) -> &'static str {
+        self.description
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn check(&self, compose: &ComposeYaml) -> Vec<Violation> {
+        let defined = compose.get_root_element_names(self.element);
+        services(compose)
+            .flat_map(|(name, service)| {
+                let mut references = self.get_references(compose, service);
+                references.dedup();
+                references
+                    .into_iter()
+                    .filter(|r| !defined.contains(&r.as_str()))
+                    .map(|r| {
+                        let attribute = service.get(self.element).unwrap_or(&Value::Null);
+                        self.violation(
+                            reference_path(name, self.element, attribute, &r),
+                            format!(
+                                "service '{}' uses undefined {} '{}'",
+                                name, self.resource, r
+                            ),
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>()
+    }
+}
@@ -2,183 +2,393 @@
 
 use clap::Parser;
 use colored::*;
-use std::{fs, process};
+use std::{env, fs, process, slice};
 
 //mod lib;
 //use crate::lib::ComposeYaml;
 use docker_pose::{
-    cmd_get_success_output_or_fail, get_and_save, get_service, get_slug, get_yml_content,
-    print_names, unwrap_filter_regex, unwrap_filter_tag, Args, Commands, ComposeYaml,
-    DockerCommand, GitCommand, Objects, ReplaceTag, Verbosity,
+    cmd_get_success_output, diff_envs, find_mount_issues, find_port_collisions, get_and_save,
+    get_cache_dir, get_compose_filename, get_project_dir, get_service, get_slug, get_yml_content,
+    has_url_placeholders, locate_violations, print_names, print_records, print_table, to_sarif,
+    unwrap_filter_regex, unwrap_filter_tag, validate_compose_file, Args, Commands, ComposeYaml,
+    Credentials, DependencyGraph, DiffObjects, DockerCommand, EnvVar, Fallback, Formats,
+    GetOptions, GitCommand, GraphFormats, Interpolator, LintConfig, LintFormats, Linter, Netrc,
+    Objects, PinDigest, PoseError, ProxyConfig, ReplaceTag, Severity, Verbosity, LINT_CONFIG_FILE,
 };
+use std::path::Path;
 
 fn main() {
     setup_terminal();
     let args = Args::parse();
+    if let Err(e) = run(args) {
+        eprintln!("{}: {}", "ERROR".red(), e);
+        process::exit(e.exit_code());
+    }
+}
+
+fn run(args: Args) -> Result<(), PoseError> {
     let verbosity = args.get_verbosity();
     // TODO check here Commands::Get to avoid compose parsing
     if let Commands::Slug { text } = args.command {
         if let Some(t) = text {
             println!("{}", get_slug(&t));
         } else {
-            let command = GitCommand::new(verbosity.clone());
-            let result_output = command.get_current_branch();
-            match result_output {
-                Ok(output) => {
-                    // git was successfully called by pose, but docker compose
-                    // could either succeed or fail executing its task
-                    let slug = cmd_get_success_output_or_fail(
-                        &command.git_bin,
-                        "rev-parse",
-                        output,
-                        args.quiet,
-                    );
-                    println!("{}", get_slug(&slug));
-                }
-                Err(e) => {
-                    // git couldn't be called by pose or the OS
-                    eprintln!("{}: calling git: {}", "ERROR".red(), e);
-                    process::exit(21);
-                }
-            }
+            let branch = get_current_branch(verbosity.clone(), args.quiet)?;
+            println!("{}", get_slug(&branch));
         }
-        process::exit(0)
+        return Ok(());
     } else if let Commands::Get {
         url,
         script,
+        fallback,
         output,
         timeout_connect,
         max_time,
         headers,
+        user,
+        bearer_token_env,
+        netrc,
+        netrc_file,
+        cacert,
+        cert,
+        key,
+        retry,
+        retry_delay,
+        sha256,
+        checksum_url,
+        no_cache,
     } = args.command
     {
-        get_and_save(
-            &url,
-            &script,
-            &output,
+        // the positional script is tried before the other fallbacks
+        let fallbacks = script
+            .map(|(left, right)| Fallback::Script(left, right))
+            .into_iter()
+            .chain(fallback)
+            .collect();
+        let branch = match has_url_placeholders(&url) {
+            true => Some(get_current_branch(verbosity.clone(), args.quiet)?),
+            false => None,
+        };
+        let credentials = match (user, bearer_token_env) {
+            (Some((user, password)), _) => Some(Credentials::Basic(user, password)),
+            (None, Some(var)) => match env::var(&var) {
+                Ok(token) if !token.is_empty() => Some(Credentials::Bearer(token)),
+                _ => {
+                    return Err(PoseError::InvalidArgument(format!(
+                        "environment variable {} with the bearer token not set",
+                        var
+                    )))
+                }
+            },
+            (None, None) => None,
+        };
+        // the .netrc file is only read if asked, so its credentials are not sent inadvertently
+        let netrc = match (&credentials, netrc_file) {
+            (Some(_), _) => None,
+            (None, Some(file)) => match Netrc::load(Path::new(&file)) {
+                Ok(Some(netrc)) => Some(netrc),
+                Ok(None) => {
+                    return Err(PoseError::NotFound(format!(
+                        "{}: no such file or directory",
+                        file
+                    )))
+                }
+                Err(e) => return Err(PoseError::ReadFile(e)),
+            },
+            (None, None) if netrc => Netrc::default_path()
+                .map(|path| Netrc::load(&path))
+                .transpose()
+                .map_err(PoseError::ReadFile)?
+                .flatten(),
+            (None, None) => None,
+        };
+        let options = GetOptions {
+            fallbacks,
+            branch,
+            output,
             timeout_connect,
             max_time,
-            &headers,
-            verbosity.clone(),
-        );
-        process::exit(0)
+            headers,
+            credentials,
+            netrc,
+            proxy: ProxyConfig::from_env(),
+            cacert,
+            cert,
+            key,
+            retry,
+            retry_delay,
+            sha256,
+            checksum_url,
+            no_cache,
+            verbosity: verbosity.clone(),
+        };
+        get_and_save(&url, &options)?;
+        return Ok(());
+    } else if let Commands::Validate = args.command {
+        return validate(&args, verbosity);
     }
-    if args.filenames.len() > 1 && args.no_docker {
-        eprintln!(
-            "{}: multiple '{}' arguments cannot be used with '{}'",
-            "ERROR".red(),
-            "--file".yellow(),
-            "--no-docker".yellow()
+    // only the services and images listed, and the config rendered,
+    // are filtered with the profiles passed
+    let filter_profiles = !args.profiles.is_empty()
+        && matches!(
+            args.command,
+            Commands::List {
+                object: Objects::Services | Objects::Images { .. },
+                ..
+            } | Commands::Config { .. }
         );
-        process::exit(2);
-    }
-    let yaml_content = match args.no_docker {
-        true => get_yml_content(args.filenames.first().map(AsRef::as_ref), verbosity.clone()),
-        false => {
-            let command = DockerCommand::new(verbosity.clone());
-            let result_output = command.call_compose_config(
-                &args.filenames.iter().map(AsRef::as_ref).collect::<Vec<_>>(),
-                args.no_consistency,
-                false,
-                false,
-            );
-            match result_output {
-                Ok(output) => {
-                    // docker was successfully called by pose, but docker compose
-                    // could either succeed or fail executing its task
-                    cmd_get_success_output_or_fail(
-                        &command.docker_bin,
-                        "compose",
-                        output,
-                        args.quiet,
-                    )
-                }
-                Err(e) => {
-                    // docker couldn't be called by pose or the OS
-                    eprintln!("{}: calling compose: {}", "ERROR".red(), e);
-                    eprintln!(
-                        "{}: parsing will be executed without compose",
-                        "WARN".yellow()
-                    );
-                    get_yml_content(args.filenames.first().map(AsRef::as_ref), verbosity.clone())
-                }
-            }
-        }
+    let mut compose = load_compose(&args, &args.filenames, filter_profiles, verbosity.clone())?;
+    // the file to compare with is loaded like the main compose file, so
+    // only the differences between the files are reported
+    let against_compose = match &args.command {
+        Commands::Diff {
+            object:
+                DiffObjects::Envs {
+                    against: Some(file),
+                    ..
+                },
+            ..
+        } => Some(load_compose(
+            &args,
+            slice::from_ref(file),
+            false,
+            verbosity.clone(),
+        )?),
+        _ => None,
     };
-    let mut compose = ComposeYaml::new(&yaml_content).unwrap_or_else(|err| {
-        if err.to_string().starts_with("invalid type") {
-            eprintln!(
-                "{}: parsing compose YAML file: invalid content",
-                "ERROR".red()
-            );
-            process::exit(13);
-        }
-        eprintln!("{}: parsing YAML file: {}", "ERROR".red(), err);
-        process::exit(15);
-    });
     match args.command {
         Commands::List { object, pretty } => match object {
-            Objects::Envs { service } => {
-                let serv = get_service(&compose, &service);
-                let envs_op = compose.get_service_envs(serv);
-                if let Some(envs) = envs_op {
-                    envs.iter().for_each(|env| println!("{}", env));
-                }
-            }
-            Objects::Depends { service } => {
-                let serv = get_service(&compose, &service);
-                let deps_op = compose.get_service_depends_on(serv);
-                if let Some(envs) = deps_op {
-                    envs.iter().for_each(|env| println!("{}", env));
-                }
-            }
-            Objects::Profiles => {
-                let op = compose.get_profiles_names();
-                match op {
-                    None => {
-                        eprintln!("{}: No profiles section found", "ERROR".red());
-                        process::exit(15);
+            Objects::Envs {
+                service,
+                with_env_files,
+                show_source,
+            } => {
+                let project_dir = get_project_dir(&args.filenames);
+                let envs = get_envs(&compose, &service, with_env_files, &project_dir)?;
+                match pretty {
+                    Formats::Full => envs.iter().for_each(|env| match show_source {
+                        true => println!("{}  # {}", env, env.source),
+                        false => println!("{}", env),
+                    }),
+                    Formats::Oneline => {
+                        let envs = envs.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+                        println!("{}", envs.join(" "));
+                    }
+                    Formats::Json | Formats::Yaml | Formats::Tsv => {
+                        print_records(&envs, pretty, |env| {
+                            let mut row =
+                                vec![env.key.clone(), env.value.clone().unwrap_or_default()];
+                            if show_source {
+                                row.push(env.source.clone());
+                            }
+                            row
+                        })?;
+                    }
+                }
+            }
+            Objects::Depends {
+                service,
+                recursive,
+                reverse,
+            } => {
+                let serv = get_service(&compose, &service)?;
+                let deps = match recursive || reverse {
+                    true => DependencyGraph::new(&compose)
+                        .get_related_services(&service, recursive, reverse)?,
+                    false => compose.get_service_depends_on(serv).unwrap_or_default(),
+                };
+                print_names(
+                    deps.iter()
+                        .map(|d| d.as_str())
+                        .collect::<Vec<_>>()
+                        .into_iter(),
+                    pretty,
+                )?;
+            }
+            Objects::Profiles { services: false } => {
+                let profiles = compose
+                    .get_profiles_names()
+                    .ok_or(PoseError::MissingSection("profiles".to_string()))?;
+                print_names(profiles.into_iter(), pretty)?;
+            }
+            Objects::Profiles { services: true } => {
+                let profiles = compose.get_profiles_services();
+                match pretty {
+                    Formats::Full => profiles
+                        .iter()
+                        .for_each(|p| println!("{}: {}", p.profile, p.services.join(" "))),
+                    _ => print_records(&profiles, pretty, |p| {
+                        vec![p.profile.clone(), p.services.join(",")]
+                    })?,
+                }
+            }
+            Objects::Mounts { service } => {
+                let mut mounts = compose.get_mounts();
+                if let Some(service) = &service {
+                    get_service(&compose, service)?;
+                    mounts.retain(|m| m.service == *service);
+                }
+                match pretty {
+                    Formats::Full => print_table(
+                        &["TYPE", "SOURCE", "TARGET", "MODE", "CONSISTENCY", "SERVICE"],
+                        mounts
+                            .iter()
+                            .map(|m| {
+                                vec![
+                                    m.mount.mount_type.to_string(),
+                                    m.mount.source.clone().unwrap_or("-".to_string()),
+                                    m.mount.target.clone(),
+                                    match m.mount.read_only {
+                                        true => "ro".to_string(),
+                                        false => "rw".to_string(),
+                                    },
+                                    m.mount.consistency.clone().unwrap_or("-".to_string()),
+                                    m.service.clone(),
+                                ]
+                            })
+                            .collect(),
+                    ),
+                    Formats::Oneline => {
+                        let mounts = mounts
+                            .iter()
+                            .map(|m| m.mount.to_string())
+                            .collect::<Vec<_>>();
+                        println!("{}", mounts.join(" "));
                     }
-                    Some(profiles) => {
-                        print_names(profiles.into_iter(), pretty);
+                    Formats::Json | Formats::Yaml | Formats::Tsv => {
+                        print_records(&mounts, pretty, |m| {
+                            vec![
+                                m.mount.mount_type.to_string(),
+                                m.mount.source.clone().unwrap_or_default(),
+                                m.mount.target.clone(),
+                                m.mount.read_only.to_string(),
+                                m.mount.consistency.clone().unwrap_or_default(),
+                                m.service.clone(),
+                            ]
+                        })?;
+                    }
+                }
+                if !args.quiet {
+                    // the volumes that can't be parsed are not listed, but not hidden either
+                    for (name, e) in compose.get_invalid_mounts() {
+                        if service.as_ref().is_none_or(|s| *s == name) {
+                            eprintln!("{}: volume of service '{}': {}", "WARN".yellow(), name, e);
+                        }
+                    }
+                    let volumes = compose.get_root_element_names("volumes");
+                    let project_dir = get_project_dir(&args.filenames);
+                    for (mount, issue) in find_mount_issues(&mounts, &volumes, &project_dir) {
+                        eprintln!(
+                            "{}: volume {} of service '{}': {}",
+                            "WARN".yellow(),
+                            mount.mount,
+                            mount.service,
+                            issue
+                        );
                     }
                 }
             }
+            Objects::Ports => {
+                let ports = compose.get_ports();
+                match pretty {
+                    Formats::Full => print_table(
+                        &[
+                            "HOST IP",
+                            "HOST PORT",
+                            "CONTAINER PORT",
+                            "PROTOCOL",
+                            "SERVICE",
+                        ],
+                        ports
+                            .iter()
+                            .map(|p| {
+                                vec![
+                                    p.port.host_ip.clone().unwrap_or("0.0.0.0".to_string()),
+                                    p.port
+                                        .published
+                                        .map(|r| r.to_string())
+                                        .unwrap_or("-".to_string()),
+                                    p.port.target.to_string(),
+                                    p.port.protocol.clone(),
+                                    p.service.clone(),
+                                ]
+                            })
+                            .collect(),
+                    ),
+                    Formats::Oneline => {
+                        let ports = ports.iter().map(|p| p.port.to_string()).collect::<Vec<_>>();
+                        println!("{}", ports.join(" "));
+                    }
+                    Formats::Json | Formats::Yaml | Formats::Tsv => {
+                        print_records(&ports, pretty, |p| {
+                            vec![
+                                p.port.host_ip.clone().unwrap_or_default(),
+                                p.port.published.map(|r| r.to_string()).unwrap_or_default(),
+                                p.port.target.to_string(),
+                                p.port.protocol.clone(),
+                                p.service.clone(),
+                            ]
+                        })?;
+                    }
+                }
+                let collisions = find_port_collisions(&ports);
+                for (port, other) in &collisions {
+                    eprintln!(
+                        "{}: port {} of service '{}' collides with port {} of service '{}'",
+                        "ERROR".red(),
+                        port.port,
+                        port.service,
+                        other.port,
+                        other.service
+                    );
+                }
+                if !collisions.is_empty() {
+                    return Err(PoseError::PortCollisions(collisions.len()));
+                }
+            }
             Objects::Images {
                 filter,
-                tag,
+                tags,
                 tag_filter,
                 ignore_unauthorized,
                 progress,
                 no_slug,
                 offline,
                 threads,
+                no_cache,
+                cache_dir,
             } => {
-                let regex = unwrap_filter_regex(tag_filter.as_deref());
-                let replace_tag = tag.map(|tag| ReplaceTag {
-                    tag,
+                let regex = unwrap_filter_regex(tag_filter.as_deref())?;
+                let replace_tag = (!tags.is_empty()).then(|| ReplaceTag {
+                    tags,
                     ignore_unauthorized,
                     threads,
                     no_slug,
                     offline,
                     tag_filter: regex,
+                    cache_dir: get_cache_dir(no_cache, cache_dir),
                     verbosity: verbosity.clone(),
                     progress_verbosity: match progress {
                         true => Verbosity::Verbose,
                         false => Verbosity::Quiet,
                     },
                 });
-                let filter_by_tag = unwrap_filter_tag(filter.as_deref());
-                let op = compose.get_images(filter_by_tag, replace_tag.as_ref());
-                match op {
-                    None => {
-                        eprintln!("{}: No services section found", "ERROR".red());
-                        process::exit(15);
-                    }
-                    Some(images) => {
-                        let images_list = images.iter().map(|i| i.as_str()).collect::<Vec<_>>();
-                        print_names(images_list.into_iter(), pretty);
-                    }
+                let filter_by_tag = unwrap_filter_tag(filter.as_deref())?;
+                let service_images = compose
+                    .get_service_images(filter_by_tag, replace_tag.as_ref())?
+                    .ok_or(PoseError::MissingSection("services".to_string()))?;
+                if pretty.is_structured() {
+                    print_records(&service_images, pretty, |i| {
+                        vec![i.service.clone(), i.image.clone(), i.resolved.clone()]
+                    })?;
+                } else {
+                    let mut images = service_images
+                        .iter()
+                        .map(|i| i.resolved.as_str())
+                        .collect::<Vec<_>>();
+                    images.sort();
+                    images.dedup();
+                    print_names(images.into_iter(), pretty)?;
                 }
             }
             Objects::Services
@@ -188,27 +398,75 @@ fn main() {
             | Objects::Secrets => {
                 let root_element = object.to_string().to_lowercase();
                 let el_iter = compose.get_root_element_names(&root_element).into_iter();
-                print_names(el_iter, pretty);
+                print_names(el_iter, pretty)?;
+            }
+        },
+        Commands::Diff { object, pretty } => match object {
+            DiffObjects::Envs {
+                service,
+                other_service,
+                against,
+                with_env_files,
+                mask,
+            } => {
+                let project_dir = get_project_dir(&args.filenames);
+                let envs = get_envs(&compose, &service, with_env_files, &project_dir)?;
+                let other_service = other_service.unwrap_or(service);
+                let other_envs = match (against, &against_compose) {
+                    (Some(file), Some(other)) => {
+                        let other_dir = get_project_dir(slice::from_ref(&file));
+                        get_envs(other, &other_service, with_env_files, &other_dir)?
+                    }
+                    _ => get_envs(&compose, &other_service, with_env_files, &project_dir)?,
+                };
+                let diff = diff_envs(&envs, &other_envs)
+                    .into_iter()
+                    .map(|d| match mask {
+                        true => d.masked(),
+                        false => d,
+                    })
+                    .collect::<Vec<_>>();
+                match pretty {
+                    Formats::Full => diff.iter().for_each(|d| println!("{}", d)),
+                    _ => print_records(&diff, pretty, |d| {
+                        vec![
+                            d.change.to_string(),
+                            d.key.clone(),
+                            d.left.clone().unwrap_or_default(),
+                            d.right.clone().unwrap_or_default(),
+                        ]
+                    })?,
+                }
             }
         },
         Commands::Config {
             output,
-            tag,
+            services,
+            with_deps,
+            tags,
             tag_filter,
+            pin_digests,
             ignore_unauthorized,
             progress,
             no_slug,
             offline,
             threads,
+            no_cache,
+            cache_dir,
         } => {
-            let regex = unwrap_filter_regex(tag_filter.as_deref());
-            let replace_tag = tag.map(|tag| ReplaceTag {
-                tag,
+            if !services.is_empty() {
+                // filtered first to not look up the images of the services removed
+                compose.filter_services(&services, with_deps)?;
+            }
+            let regex = unwrap_filter_regex(tag_filter.as_deref())?;
+            let replace_tag = (!tags.is_empty()).then(|| ReplaceTag {
+                tags,
                 ignore_unauthorized,
                 offline,
                 threads,
                 no_slug,
                 tag_filter: regex,
+                cache_dir: get_cache_dir(no_cache, cache_dir),
                 verbosity: verbosity.clone(),
                 progress_verbosity: match progress {
                     true => Verbosity::Verbose,
@@ -216,30 +474,306 @@ fn main() {
                 },
             });
             if let Some(remote_t) = replace_tag {
-                compose.update_images_tag(&remote_t);
+                compose.update_images_tag(&remote_t)?;
             }
-            let result = compose.to_string().unwrap_or_else(|err| {
-                eprintln!("{}: {}", "ERROR".red(), err);
-                process::exit(20);
-            });
+            if pin_digests {
+                compose.pin_images_digest(&PinDigest {
+                    ignore_unauthorized,
+                    offline,
+                    threads,
+                    verbosity: verbosity.clone(),
+                    progress_verbosity: match progress {
+                        true => Verbosity::Verbose,
+                        false => Verbosity::Quiet,
+                    },
+                })?;
+            }
+            let result = compose
+                .to_string()
+                .map_err(|err| PoseError::Serialize(err.to_string()))?;
             if let Some(file) = output {
-                fs::write(&file, result).unwrap_or_else(|e| {
-                    eprintln!(
-                        "{}: writing output to '{}' file: {}",
-                        "ERROR".red(),
-                        file.yellow(),
-                        e
-                    );
-                    process::exit(18);
-                });
+                fs::write(&file, result).map_err(|e| {
+                    PoseError::WriteOutput(format!("writing output to '{}' file: {}", file, e))
+                })?;
             } else {
                 println!("{}", result);
             }
         }
-        Commands::Slug { .. } | Commands::Get { .. } => {
+        Commands::Graph { format } => {
+            let graph = DependencyGraph::new(&compose);
+            match format {
+                GraphFormats::Order => graph
+                    .get_startup_order()?
+                    .iter()
+                    .for_each(|service| println!("{}", service)),
+                GraphFormats::Dot | GraphFormats::Mermaid => {
+                    if let Some(cycle) = graph.find_cycle() {
+                        if !args.quiet {
+                            eprintln!(
+                                "{}: circular dependency found: {}",
+                                "WARN".yellow(),
+                                cycle.join(" -> ").yellow()
+                            );
+                        }
+                    }
+                    match format {
+                        GraphFormats::Dot => print!("{}", graph.to_dot()),
+                        _ => print!("{}", graph.to_mermaid()),
+                    }
+                }
+            }
+        }
+        Commands::Lint { format, config } => {
+            lint(
+                &compose,
+                &args.filenames,
+                format,
+                config,
+                args.quiet,
+                verbosity,
+            )?;
+        }
+        Commands::Slug { .. } | Commands::Get { .. } | Commands::Validate => {
             // This was attended above in the code
         }
     }
+    Ok(())
+}
+
+/// Get the name of the current git branch.
+fn get_current_branch(verbosity: Verbosity, quiet: bool) -> Result<String, PoseError> {
+    let command = GitCommand::new(verbosity);
+    // an error here means git couldn't be called by pose or the OS
+    let output = command
+        .get_current_branch()
+        .map_err(|e| PoseError::Git(e.to_string()))?;
+    // git was successfully called by pose, but git
+    // could either succeed or fail executing its task
+    let branch = cmd_get_success_output(&command.git_bin, "rev-parse", output, quiet)?;
+    Ok(branch.trim().to_string())
+}
+
+/// Validate each compose file against the Compose specification, and
+/// then the whole model with docker compose, unless --no-docker is used.
+fn validate(args: &Args, verbosity: Verbosity) -> Result<(), PoseError> {
+    let filenames = match args.filenames.is_empty() {
+        true => vec![get_compose_filename(None, verbosity.clone())?],
+        false => args.filenames.clone(),
+    };
+    let interpolator = match args.interpolate {
+        true => {
+            let project_dir = get_project_dir(&args.filenames);
+            Some(Interpolator::from_env(
+                &project_dir,
+                &args.env_files,
+                verbosity.clone(),
+            )?)
+        }
+        false => None,
+    };
+    let mut errors_count = 0;
+    for filename in &filenames {
+        let content = get_yml_content(Some(filename), verbosity.clone())?;
+        let errors = validate_compose_file(&content, interpolator.as_ref())?;
+        for error in &errors {
+            match error.line {
+                Some(line) => println!("{}:{}: {}", filename, line, error),
+                None => println!("{}: {}", filename, error),
+            }
+        }
+        errors_count += errors.len();
+    }
+    if errors_count > 0 {
+        return Err(PoseError::InvalidSchema(errors_count));
+    }
+    if !args.no_docker {
+        // e.g. services that use networks not defined
+        let command = DockerCommand::new(verbosity.clone());
+        let result_output = command.call_compose_config(
+            &filenames.iter().map(AsRef::as_ref).collect::<Vec<_>>(),
+            &args.profiles,
+            args.no_consistency,
+            false,
+            false,
+        );
+        match result_output {
+            Ok(output) => {
+                cmd_get_success_output(&command.docker_bin, "compose", output, args.quiet)?;
+            }
+            Err(e) => {
+                // docker couldn't be called by pose or the OS
+                eprintln!("{}: calling compose: {}", "ERROR".red(), e);
+                eprintln!(
+                    "{}: model consistency not checked without compose",
+                    "WARN".yellow()
+                );
+            }
+        }
+    }
+    if matches!(verbosity, Verbosity::Verbose) {
+        eprintln!("{}: {} valid", "DEBUG".green(), filenames.join(", "));
+    }
+    Ok(())
+}
+
+/// Check the compose model with the rules of the linter not disabled in the
+/// config, locating each violation in the compose files to report it.
+fn lint(
+    compose: &ComposeYaml,
+    filenames: &[String],
+    format: LintFormats,
+    config: Option<String>,
+    quiet: bool,
+    verbosity: Verbosity,
+) -> Result<(), PoseError> {
+    let config = match config {
+        Some(file) => LintConfig::load(Path::new(&file))?,
+        None => {
+            let file = get_project_dir(filenames).join(LINT_CONFIG_FILE);
+            match file.exists() {
+                true => LintConfig::load(&file)?,
+                false => LintConfig::default(),
+            }
+        }
+    };
+    let linter = Linter::default();
+    if !quiet {
+        for rule_id in config.get_unknown_rules(&linter) {
+            eprintln!("{}: unknown lint rule '{}'", "WARN".yellow(), rule_id);
+        }
+    }
+    let filenames = match filenames.is_empty() {
+        true => vec![get_compose_filename(None, verbosity.clone())?],
+        false => filenames.to_vec(),
+    };
+    let files = filenames
+        .into_iter()
+        .map(|filename| {
+            let content = get_yml_content(Some(&filename), verbosity.clone())?;
+            Ok((filename, content))
+        })
+        .collect::<Result<Vec<_>, PoseError>>()?;
+    let mut violations = linter.lint(compose, &config);
+    locate_violations(&mut violations, &files);
+    violations.retain(|v| !config.is_disabled(&v.rule_id, v.file.as_deref()));
+    violations.sort_by(|a, b| (&a.file, a.line).cmp(&(&b.file, b.line)));
+    match format {
+        LintFormats::Text => {
+            for violation in &violations {
+                match (&violation.file, violation.line) {
+                    (Some(file), Some(line)) => println!("{}:{}: {}", file, line, violation),
+                    (Some(file), None) => println!("{}: {}", file, violation),
+                    _ => println!("{}", violation),
+                }
+            }
+        }
+        LintFormats::Sarif => {
+            let report = serde_json::to_string_pretty(&to_sarif(&linter, &violations))
+                .map_err(|e| PoseError::Serialize(e.to_string()))?;
+            println!("{}", report);
+        }
+    }
+    let errors_count = violations
+        .iter()
+        .filter(|v| v.severity == Severity::Error)
+        .count();
+    match errors_count {
+        0 => Ok(()),
+        n => Err(PoseError::LintErrors(n)),
+    }
+}
+
+/// Get the variables of the service, including the ones from
+/// the env files with `with_env_files`.
+fn get_envs(
+    compose: &ComposeYaml,
+    service: &str,
+    with_env_files: bool,
+    project_dir: &Path,
+) -> Result<Vec<EnvVar>, PoseError> {
+    let serv = get_service(compose, service)?;
+    match with_env_files {
+        true => compose.get_service_env_vars_with_files(serv, project_dir),
+        false => Ok(compose.get_service_env_vars(serv).unwrap_or_default()),
+    }
+}
+
+/// Load the compose files passed with `docker compose config`, or with pose if
+/// docker is not used or can't be called, merging and interpolating the files,
+/// and filtering the services with the profiles enabled if `filter_profiles`.
+fn load_compose(
+    args: &Args,
+    filenames: &[String],
+    filter_profiles: bool,
+    verbosity: Verbosity,
+) -> Result<ComposeYaml, PoseError> {
+    let yaml_contents = match args.no_docker {
+        true => get_yml_contents(filenames, verbosity.clone())?,
+        false => {
+            let command = DockerCommand::new(verbosity.clone());
+            let result_output = command.call_compose_config(
+                &filenames.iter().map(AsRef::as_ref).collect::<Vec<_>>(),
+                &args.profiles,
+                args.no_consistency,
+                false,
+                false,
+            );
+            match result_output {
+                Ok(output) => {
+                    // docker was successfully called by pose, but docker compose
+                    // could either succeed or fail executing its task
+                    vec![cmd_get_success_output(
+                        &command.docker_bin,
+                        "compose",
+                        output,
+                        args.quiet,
+                    )?]
+                }
+                Err(e) => {
+                    // docker couldn't be called by pose or the OS
+                    eprintln!("{}: calling compose: {}", "ERROR".red(), e);
+                    eprintln!(
+                        "{}: parsing will be executed without compose",
+                        "WARN".yellow()
+                    );
+                    get_yml_contents(filenames, verbosity.clone())?
+                }
+            }
+        }
+    };
+    // when compose is not used and multiple files are passed,
+    // the files are merged by pose following the Compose specification
+    let mut yaml_iter = yaml_contents.iter();
+    let mut compose = parse_compose(yaml_iter.next().unwrap())?;
+    for yaml_content in yaml_iter {
+        compose.merge(parse_compose(yaml_content)?);
+    }
+    if args.interpolate {
+        let project_dir = get_project_dir(filenames);
+        let interpolator = Interpolator::from_env(&project_dir, &args.env_files, verbosity)?;
+        compose.interpolate(&interpolator)?;
+    }
+    if filter_profiles {
+        compose.filter_profiles(&args.profiles);
+    }
+    Ok(compose)
+}
+
+fn get_yml_contents(filenames: &[String], verbosity: Verbosity) -> Result<Vec<String>, PoseError> {
+    if filenames.is_empty() {
+        return Ok(vec![get_yml_content(None, verbosity)?]);
+    }
+    filenames
+        .iter()
+        .map(|filename| get_yml_content(Some(filename), verbosity.clone()))
+        .collect()
+}
+
+fn parse_compose(yaml_content: &str) -> Result<ComposeYaml, PoseError> {
+    ComposeYaml::new(yaml_content).map_err(|err| match err.to_string() {
+        e if e.starts_with("invalid type") => PoseError::InvalidCompose,
+        e => PoseError::ParseYaml(e),
+    })
 }
 
 #[cfg(target_os = "windows")]
@@ -0,0 +1,192 @@
+/// Merge of compose models following the rules of the Compose specification,
+/// used when multiple files are passed and `docker compose` is not used:
+/// https://github.com/compose-spec/compose-spec/blob/master/13-merge.md
+use serde_yaml::{Mapping, Sequence, Value};
+
+/// Attributes of a service that are replaced instead of merged
+/// when defined as a sequence.
+static REPLACED_ATTRS: [&str; 2] = ["command", "entrypoint"];
+
+/// Attributes of a service that can be defined as a sequence of `KEY=VALUE`
+/// or as a mapping, and are merged by key.
+static KEY_VALUE_ATTRS: [&str; 4] = ["environment", "labels", "annotations", "sysctls"];
+
+/// Attributes of a service that are sequences with unique elements,
+/// when merged, elements with the same key are replaced.
+static UNIQUE_ATTRS: [&str; 4] = ["ports", "volumes", "secrets", "configs"];
+
+/// Merge the `other` value into `base`, where `path` are the keys from
+/// the root of the document to the values merged, e.g. `["services", "app", "ports"]`.
+///
+/// - Mappings are merged recursively.
+/// - Sequences are appended, except `command` and `entrypoint` (and the `test`
+///   of a `healthcheck`) that are replaced, and the "unique resources"
+///   `ports`, `volumes`, `secrets` and `configs` where elements with the same
+///   key are replaced.
+/// - `environment`, `labels`, `annotations` and `sysctls` are merged by key.
+/// - Any other value is replaced.
+/// - Values tagged with `!reset` remove the attribute, and values tagged
+///   with `!override` replace the attribute instead of merging it.
+pub fn merge_yaml(base: &mut Value, other: Value, path: &[String]) {
+    if is_tagged(&other, "override") {
+        *base = untag(other);
+        return;
+    }
+    let other = untag(other);
+    let service_attr = match path {
+        [services, _, attr] if services == "services" => Some(attr.as_str()),
+        _ => None,
+    };
+    let is_healthcheck_test = matches!(
+        path,
+        [services, _, healthcheck, test]
+        if services == "services" && healthcheck == "healthcheck" && test == "test"
+    );
+    if let Some(attr) = service_attr {
+        if KEY_VALUE_ATTRS.contains(&attr) {
+            merge_key_values(base, other);
+            return;
+        }
+    }
+    match (base, other) {
+        (Value::Mapping(base_map), Value::Mapping(other_map)) => {
+            merge_mappings(base_map, other_map, path);
+        }
+        (Value::Sequence(base_seq), Value::Sequence(other_seq)) => match service_attr {
+            Some(attr) if REPLACED_ATTRS.contains(&attr) => *base_seq = other_seq,
+            Some(attr) if UNIQUE_ATTRS.contains(&attr) => {
+                merge_unique(base_seq, other_seq, attr);
+            }
+            _ if is_healthcheck_test => *base_seq = other_seq,
+            _ => base_seq.extend(other_seq),
+        },
+        (base, other) => *base = other,
+    }
+}
+
+fn merge_mappings(base: &mut Mapping, other: Mapping, path: &[String]) {
+    for (key, value) in other {
+        if is_tagged(&value, "reset") {
+            base.remove(&key);
+            continue;
+        }
+        match base.get_mut(&key) {
+            Some(base_value) => {
+                let mut key_path = path.to_vec();
+                key_path.push(key.as_str().unwrap_or_default().to_string());
+                merge_yaml(base_value, value, &key_path);
+            }
+            None => {
+                base.insert(key, untag(value));
+            }
+        }
+    }
+}
+
+/// Merge attributes that can be expressed as a sequence of `KEY=VALUE`
+/// or as a mapping. If both are sequences the result is a sequence,
+/// otherwise the result is a mapping.
+fn merge_key_values(base: &mut Value, other: Value) {
+    match (base, other) {
+        (Value::Sequence(base_seq), Value::Sequence(other_seq)) => {
+            for el in other_seq {
+                let key = key_value_key(&el);
+                match base_seq.iter_mut().find(|e| key_value_key(e) == key) {
+                    Some(base_el) => *base_el = el,
+                    None => base_seq.push(el),
+                }
+            }
+        }
+        (base, other) => {
+            let mut base_map = key_values_to_mapping(base);
+            for (key, value) in key_values_to_mapping(&other) {
+                base_map.insert(key, value);
+            }
+            *base = Value::Mapping(base_map);
+        }
+    }
+}
+
+fn key_value_key(el: &Value) -> String {
+    el.as_str()
+        .map(|s| s.split('=').next().unwrap_or_default())
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn key_values_to_mapping(value: &Value) -> Mapping {
+    match value {
+        Value::Sequence(seq) => seq
+            .iter()
+            .flat_map(|el| el.as_str())
+            .map(|s| match s.split_once('=') {
+                Some((key, val)) => (Value::from(key), Value::from(val)),
+                None => (Value::from(s), Value::Null),
+            })
+            .collect(),
+        Value::Mapping(map) => map.clone(),
+        _ => Mapping::default(),
+    }
+}
+
+/// Merge sequences where each element is identified by a key, elements
+/// from `other` replace the ones from `base` with the same key.
+fn merge_unique(base: &mut Sequence, other: Sequence, attr: &str) {
+    for el in other {
+        let key = unique_key(&el, attr);
+        match base.iter_mut().find(|e| unique_key(e, attr) == key) {
+            Some(base_el) => *base_el = el,
+            None => base.push(el),
+        }
+    }
+}
+
+/// Get the key that identifies an element of the unique resources:
+/// the target for volumes, secrets and configs, and the ip, published port,
+/// target port and protocol for ports.
+fn unique_key(el: &Value, attr: &str) -> String {
+    match el {
+        Value::Mapping(map) => {
+            let get = |k: &str| match map.get(k) {
+                Some(Value::String(s)) => s.to_string(),
+                Some(Value::Number(n)) => n.to_string(),
+                _ => String::new(),
+            };
+            match attr {
+                "ports" => format!(
+                    "{}:{}:{}/{}",
+                    get("host_ip"),
+                    get("published"),
+                    get("target"),
+                    Some(get("protocol"))
+                        .filter(|p| !p.is_empty())
+                        .unwrap_or("tcp".to_string())
+                ),
+                "volumes" => get("target"),
+                _ => Some(get("target"))
+                    .filter(|t| !t.is_empty())
+                    .unwrap_or_else(|| get("source")),
+            }
+        }
+        Value::String(s) => match attr {
+            "volumes" => {
+                let parts = s.split(':').collect::<Vec<_>>();
+                parts.get(1).unwrap_or(&parts[0]).to_string()
+            }
+            _ => s.to_string(),
+        },
+        Value::Number(n) => n.to_string(),
+        _ => String::new(),
+    }
+}
+
+fn is_tagged(value: &Value, tag: &str) -> bool {
+    matches!(value, Value::Tagged(tagged) if tagged.tag == tag)
+}
+
+fn untag(value: Value) -> Value {
+    match value {
+        Value::Tagged(tagged) => tagged.value,
+        _ => value,
+    }
+}
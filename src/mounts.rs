@@ -0,0 +1,210 @@
+/// Parser of the volumes mounted by the services, in the short syntax
+/// `[SOURCE:]TARGET[:MODE]` or the long syntax, and the `tmpfs` attribute.
+use serde::Serialize;
+use serde_yaml::Value;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, strum_macros::Display)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum MountType {
+    Bind,
+    Volume,
+    Tmpfs,
+    Npipe,
+    Cluster,
+    Image,
+}
+
+/// A volume mounted by a service.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Mount {
+    #[serde(rename = "type")]
+    pub mount_type: MountType,
+    /// path in the host, name of the volume or image, `None` for anonymous volumes and tmpfs
+    pub source: Option<String>,
+    /// path in the container
+    pub target: String,
+    pub read_only: bool,
+    /// consistency requirements of bind mounts: `consistent`, `cached` or `delegated`
+    pub consistency: Option<String>,
+}
+
+impl Mount {
+    /// Parse a volume in the short syntax.
+    ///
+    /// ```
+    /// use docker_pose::{Mount, MountType};
+    ///
+    /// let mount = Mount::parse("./data:/data:ro,cached").unwrap();
+    /// assert_eq!(mount.mount_type, MountType::Bind);
+    /// assert_eq!(mount.source, Some("./data".to_string()));
+    /// assert_eq!(mount.target, "/data");
+    /// assert!(mount.read_only);
+    /// assert_eq!(mount.consistency, Some("cached".to_string()));
+    ///
+    /// let mount = Mount::parse("db-data:/var/lib/postgresql/data").unwrap();
+    /// assert_eq!(mount.mount_type, MountType::Volume);
+    /// assert!(!mount.read_only);
+    ///
+    /// // anonymous volume
+    /// let mount = Mount::parse("/var/lib/data").unwrap();
+    /// assert_eq!(mount.mount_type, MountType::Volume);
+    /// assert_eq!(mount.source, None);
+    /// ```
+    pub fn parse(volume: &str) -> Result<Self, String> {
+        let invalid = |e: &str| format!("invalid volume '{}': {}", volume, e);
+        // Windows paths like C:\data have a ':' after the drive letter
+        let drive_len = match volume.as_bytes() {
+            [letter, b':', b'\\' | b'/', ..] if letter.is_ascii_alphabetic() => 2,
+            _ => 0,
+        };
+        let (drive, rest) = volume.split_at(drive_len);
+        let parts = rest.split(':').collect::<Vec<_>>();
+        let (source, target, mode) = match parts[..] {
+            [target] if drive.is_empty() => (None, target, None),
+            [source, target] => (Some(format!("{}{}", drive, source)), target, None),
+            [source, target, mode] => (Some(format!("{}{}", drive, source)), target, Some(mode)),
+            _ => return Err(invalid("wrong format")),
+        };
+        if target.is_empty() {
+            return Err(invalid("empty target"));
+        }
+        let options = mode.unwrap_or_default().split(',').collect::<Vec<_>>();
+        Ok(Self {
+            mount_type: match source.as_deref() {
+                Some(source) if !is_named_volume(source) => MountType::Bind,
+                _ => MountType::Volume,
+            },
+            source: source.filter(|s| !s.is_empty()),
+            target: target.to_string(),
+            read_only: options.contains(&"ro"),
+            consistency: options
+                .iter()
+                .find(|o| matches!(**o, "consistent" | "cached" | "delegated"))
+                .map(|o| o.to_string()),
+        })
+    }
+
+    /// Parse a volume from the `volumes` attribute of a service,
+    /// in the short or in the long syntax.
+    pub fn from_value(value: &Value) -> Result<Self, String> {
+        match value {
+            Value::String(volume) => Self::parse(volume),
+            Value::Mapping(volume) => {
+                let field = |name: &str| volume.get(name).and_then(|v| v.as_str());
+                let mount_type = match field("type") {
+                    Some("bind") => MountType::Bind,
+                    Some("volume") => MountType::Volume,
+                    Some("tmpfs") => MountType::Tmpfs,
+                    Some("npipe") => MountType::Npipe,
+                    Some("cluster") => MountType::Cluster,
+                    Some("image") => MountType::Image,
+                    Some(t) => return Err(format!("invalid volume type '{}'", t)),
+                    None => return Err("volume without type".to_string()),
+                };
+                Ok(Self {
+                    mount_type,
+                    source: field("source").map(String::from),
+                    target: field("target").ok_or("volume without target")?.to_string(),
+                    read_only: match volume.get("read_only") {
+                        Some(Value::Bool(read_only)) => *read_only,
+                        Some(Value::String(read_only)) => read_only == "true",
+                        _ => false,
+                    },
+                    consistency: field("consistency").map(String::from),
+                })
+            }
+            _ => Err("invalid volume definition".to_string()),
+        }
+    }
+
+    /// Parse a mount from the `tmpfs` attribute of a service: `TARGET[:OPTIONS]`.
+    pub fn from_tmpfs(tmpfs: &str) -> Self {
+        let target = tmpfs.split_once(':').map(|(t, _)| t).unwrap_or(tmpfs);
+        Self {
+            mount_type: MountType::Tmpfs,
+            source: None,
+            target: target.to_string(),
+            read_only: false,
+            consistency: None,
+        }
+    }
+
+    /// Path in the host of a bind mount, relative to the project directory
+    /// if it's not absolute, `None` if the path has variables not interpolated.
+    pub fn get_host_path(&self, project_dir: &Path) -> Option<PathBuf> {
+        let source = self.source.as_deref()?;
+        if self.mount_type != MountType::Bind || source.contains('$') {
+            return None;
+        }
+        match source.strip_prefix("~/") {
+            Some(path) => std::env::var("HOME")
+                .ok()
+                .map(|home| Path::new(&home).join(path)),
+            None => Some(project_dir.join(source.strip_prefix("./").unwrap_or(source))),
+        }
+    }
+}
+
+impl fmt::Display for Mount {
+    /// Format the mount in the short syntax.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(source) = &self.source {
+            write!(f, "{}:", source)?;
+        }
+        write!(f, "{}", self.target)?;
+        let options = [self.read_only.then_some("ro"), self.consistency.as_deref()];
+        let options = options.iter().flatten().copied().collect::<Vec<_>>();
+        match options.is_empty() {
+            true => Ok(()),
+            false => write!(f, ":{}", options.join(",")),
+        }
+    }
+}
+
+/// Whether the source of a volume in the short syntax is the name of
+/// a volume instead of a path in the host.
+fn is_named_volume(source: &str) -> bool {
+    !source.is_empty() && !source.starts_with(['.', '/', '~', '$']) && !source.contains(['/', '\\'])
+}
+
+/// A volume mounted by a service, see `ComposeYaml::get_mounts`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ServiceMount {
+    #[serde(flatten)]
+    pub mount: Mount,
+    pub service: String,
+}
+
+/// Find the bind mounts with a host path that doesn't exist, and the
+/// named volumes not declared in the `volumes` section, returning each
+/// mount with the description of the problem.
+pub fn find_mount_issues<'a>(
+    mounts: &'a [ServiceMount],
+    volumes: &[&str],
+    project_dir: &Path,
+) -> Vec<(&'a ServiceMount, String)> {
+    mounts
+        .iter()
+        .flat_map(|m| {
+            let source = m.mount.source.as_deref().unwrap_or_default();
+            let issue = match m.mount.mount_type {
+                MountType::Bind => m
+                    .mount
+                    .get_host_path(project_dir)
+                    .filter(|path| !path.exists())
+                    .map(|path| format!("host path {} doesn't exist", path.display())),
+                MountType::Volume if !source.is_empty() && !source.contains('$') => {
+                    match volumes.contains(&source) {
+                        true => None,
+                        false => Some(format!("volume '{}' not declared", source)),
+                    }
+                }
+                _ => None,
+            };
+            issue.map(|issue| (m, issue))
+        })
+        .collect::<Vec<_>>()
+}
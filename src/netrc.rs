@@ -0,0 +1,119 @@
+/// Parser of `.netrc` files, with the credentials of the
+/// machines used to authenticate the downloads.
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Credentials of a machine.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NetrcEntry {
+    pub login: String,
+    pub password: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Netrc {
+    machines: Vec<(String, NetrcEntry)>,
+    default: Option<NetrcEntry>,
+}
+
+impl Netrc {
+    /// Parse the content of a `.netrc` file. Macro definitions
+    /// (`macdef`) are skipped, and the tokens not supported ignored.
+    ///
+    /// ```
+    /// use docker_pose::{Netrc, NetrcEntry};
+    ///
+    /// let netrc = Netrc::parse("machine gitlab.com login ci password s3cr3t\n\
+    ///                           default login anonymous password guest");
+    /// assert_eq!(netrc.get("gitlab.com").unwrap().password, "s3cr3t");
+    /// assert_eq!(netrc.get("nexus.local").unwrap().login, "anonymous");
+    /// ```
+    pub fn parse(content: &str) -> Self {
+        let mut netrc = Self::default();
+        // name of the machine of the entry being parsed, `None` for the default entry
+        let mut current: Option<(Option<String>, NetrcEntry)> = None;
+        let mut lines = content.lines();
+        while let Some(line) = lines.next() {
+            let mut tokens = line.split_whitespace();
+            while let Some(token) = tokens.next() {
+                match token {
+                    "machine" | "default" => {
+                        netrc.push(current.take());
+                        let name = match token {
+                            "machine" => tokens.next().map(String::from),
+                            _ => None,
+                        };
+                        let entry = NetrcEntry {
+                            login: String::new(),
+                            password: String::new(),
+                        };
+                        current = Some((name, entry));
+                    }
+                    "login" | "password" => {
+                        let value = tokens.next().unwrap_or_default().to_string();
+                        if let Some((_, entry)) = current.as_mut() {
+                            match token {
+                                "login" => entry.login = value,
+                                _ => entry.password = value,
+                            }
+                        }
+                    }
+                    "account" => {
+                        tokens.next();
+                    }
+                    "macdef" => {
+                        // the macro ends with an empty line
+                        for line in lines.by_ref() {
+                            if line.trim().is_empty() {
+                                break;
+                            }
+                        }
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        netrc.push(current);
+        netrc
+    }
+
+    fn push(&mut self, entry: Option<(Option<String>, NetrcEntry)>) {
+        match entry {
+            Some((Some(machine), entry)) => self.machines.push((machine, entry)),
+            Some((None, entry)) => self.default = Some(entry),
+            None => {}
+        }
+    }
+
+    /// Load the `.netrc` file passed, `None` if it doesn't exist.
+    pub fn load(path: &Path) -> Result<Option<Self>, String> {
+        if !path.is_file() {
+            return Ok(None);
+        }
+        fs::read_to_string(path)
+            .map(|content| Some(Self::parse(&content)))
+            .map_err(|e| format!("reading netrc file '{}': {}", path.display(), e))
+    }
+
+    /// Default location of the `.netrc` file: `$NETRC` or `~/.netrc`.
+    pub fn default_path() -> Option<PathBuf> {
+        match env::var("NETRC") {
+            Ok(path) if !path.is_empty() => Some(PathBuf::from(path)),
+            _ => env::var("HOME")
+                .ok()
+                .map(|home| Path::new(&home).join(".netrc")),
+        }
+    }
+
+    /// Credentials of the machine, or the default ones if
+    /// the machine is not found.
+    pub fn get(&self, host: &str) -> Option<&NetrcEntry> {
+        self.machines
+            .iter()
+            .find(|(machine, _)| machine == host)
+            .map(|(_, entry)| entry)
+            .or(self.default.as_ref())
+    }
+}
@@ -1,30 +1,39 @@
+use crate::merge::merge_yaml;
 use crate::verbose::Verbosity;
-use crate::{get_slug, DockerCommand};
+use crate::{
+    get_repo_digest, get_slug, is_sha256, read_dotenv, DockerCommand, Fallback, ImageRef,
+    Interpolator, Mount, MountType, PortMapping, PoseError, RegistryClient, RegistryError,
+    ServiceMount, ServicePort, TagCache,
+};
 use clap_num::number_range;
 use colored::*;
 use regex::Regex;
+use serde::Serialize;
 use serde_yaml::{to_string, Error, Mapping, Value};
 use std::cmp::{max, min};
-use std::collections::BTreeMap;
-use std::path::Path;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::{Path, PathBuf};
 use std::sync::mpsc::{Receiver, Sender};
 use std::sync::{mpsc, Arc, Mutex};
-use std::{process, thread};
+use std::{env, io, thread};
 
 lazy_static! {
     static ref EMPTY_MAP: Mapping = Mapping::default();
-    static ref ENV_NAME_REGEX: Regex = Regex::new(r"^\w+$").unwrap();
     static ref QUOTED_NUM_REGEX: Regex = Regex::new(r"^'[0-9]+'$").unwrap();
 }
 
+/// an image and the result of the task run over it
+type ImageResult = (String, Result<String, PoseError>);
+
 pub struct ComposeYaml {
     map: BTreeMap<String, Value>,
 }
 
 #[derive(Clone)]
 pub struct ReplaceTag {
-    /// replace tag with local or remote tag if exists
-    pub tag: String,
+    /// replace tag with the first local or remote tag found from the list
+    pub tags: Vec<String>,
     /// don't replace with tag unless this regex matches the image name / tag,
     /// in case the bool is false, the replacing is done if the regex doesn't match
     pub tag_filter: Option<(Regex, bool)>,
@@ -41,17 +50,88 @@ pub struct ReplaceTag {
     pub progress_verbosity: Verbosity,
     /// max number of threads used to fetch remote images info
     pub threads: u8,
+    /// directory of the cache of the remote tags info, `None` to not use the cache
+    pub cache_dir: Option<PathBuf>,
 }
 
-impl ReplaceTag {
-    pub fn get_remote_tag(&self) -> String {
-        match self.no_slug {
-            true => self.tag.clone(),
-            false => get_slug(&self.tag),
+#[derive(Clone)]
+pub struct PinDigest {
+    /// docker may require to be logged-in to fetch some images info, with
+    /// `true` unauthorized errors are ignored
+    pub ignore_unauthorized: bool,
+    /// only look for the digests in the local docker registry
+    pub offline: bool,
+    /// verbosity used when fetching remote images info
+    pub verbosity: Verbosity,
+    /// show digests found while they are fetched
+    pub progress_verbosity: Verbosity,
+    /// max number of threads used to fetch remote images info
+    pub threads: u8,
+}
+
+/// The image of a service, and the image resolved when a
+/// tag is replaced, see `ComposeYaml::get_service_images`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ServiceImage {
+    pub service: String,
+    pub image: String,
+    pub resolved: String,
+}
+
+/// A profile, and the services enabled by it,
+/// see `ComposeYaml::get_profiles_services`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ProfileServices {
+    pub profile: String,
+    pub services: Vec<String>,
+}
+
+/// An environment variable of a service.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct EnvVar {
+    pub key: String,
+    /// value of the variable, `None` if the variable is declared without value
+    pub value: Option<String>,
+    /// where the variable is defined: "environment" or the path of the env file
+    pub source: String,
+    /// value as it's printed when it's different from `value`,
+    /// e.g. quoted when the value is set in a mapping
+    #[serde(skip)]
+    pub formatted: Option<String>,
+}
+
+impl EnvVar {
+    pub fn new(key: &str, value: Option<&str>, source: &str) -> Self {
+        Self {
+            key: key.to_string(),
+            value: value.map(String::from),
+            source: source.to_string(),
+            formatted: None,
         }
     }
 }
 
+impl fmt::Display for EnvVar {
+    /// Format the variable as `KEY=value`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let val = self.formatted.as_deref().or(self.value.as_deref());
+        write!(f, "{}={}", self.key, val.unwrap_or_default())
+    }
+}
+
+impl ReplaceTag {
+    /// Tags to look for, in order of priority.
+    pub fn get_remote_tags(&self) -> Vec<String> {
+        self.tags
+            .iter()
+            .map(|tag| match self.no_slug {
+                true => tag.clone(),
+                false => get_slug(tag),
+            })
+            .collect()
+    }
+}
+
 impl ComposeYaml {
     pub fn new(yaml: &str) -> Result<ComposeYaml, Error> {
         let map = serde_yaml::from_str(yaml)?;
@@ -63,6 +143,39 @@ impl ComposeYaml {
         Ok(yaml_string)
     }
 
+    /// Merge another compose model on top of this one, following the
+    /// merge rules of the Compose specification, as `docker compose`
+    /// does when multiple files are passed.
+    pub fn merge(&mut self, other: ComposeYaml) {
+        for (key, value) in other.map {
+            if let Value::Tagged(tagged) = &value {
+                if tagged.tag == "reset" {
+                    self.map.remove(&key);
+                    continue;
+                }
+            }
+            match self.map.get_mut(&key) {
+                Some(base) => merge_yaml(base, value, &[key]),
+                None => {
+                    let mut base = Value::Null;
+                    merge_yaml(&mut base, value, std::slice::from_ref(&key));
+                    self.map.insert(key, base);
+                }
+            }
+        }
+    }
+
+    /// Interpolate the variables found in all the values of the model,
+    /// returning an error with the path of the value that failed.
+    pub fn interpolate(&mut self, interpolator: &Interpolator) -> Result<(), PoseError> {
+        for (key, value) in self.map.iter_mut() {
+            interpolator
+                .interpolate_value(value, key)
+                .map_err(PoseError::Interpolation)?;
+        }
+        Ok(())
+    }
+
     pub fn get_root_element(&self, element_name: &str) -> Option<&Mapping> {
         let value = self.map.get(element_name);
         value.map(|v| v.as_mapping()).unwrap_or_default()
@@ -95,147 +208,295 @@ impl ComposeYaml {
         Some(profiles)
     }
 
+    /// Get each profile with the services that declare it
+    /// in the `profiles` attribute, sorted by profile name.
+    pub fn get_profiles_services(&self) -> Vec<ProfileServices> {
+        let mut profiles: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+        for (name, service) in self.get_services().unwrap_or(&EMPTY_MAP) {
+            let (name, service) = match (name.as_str(), service.as_mapping()) {
+                (Some(name), Some(service)) => (name, service),
+                _ => continue,
+            };
+            for profile in self.get_service_profiles(service) {
+                profiles.entry(profile).or_default().push(name.to_string());
+            }
+        }
+        profiles
+            .into_iter()
+            .map(|(profile, services)| ProfileServices {
+                profile: profile.to_string(),
+                services,
+            })
+            .collect::<Vec<_>>()
+    }
+
+    pub fn get_service_profiles<'a>(&self, service: &'a Mapping) -> Vec<&'a str> {
+        service
+            .get("profiles")
+            .and_then(|p| p.as_sequence())
+            .into_iter()
+            .flatten()
+            .flat_map(|p| p.as_str())
+            .collect::<Vec<_>>()
+    }
+
+    /// Whether the service is enabled with the profiles passed: services
+    /// without profiles are always enabled, as Compose does, and the
+    /// profile `*` enables all the services.
+    ///
+    /// ```
+    /// use docker_pose::ComposeYaml;
+    ///
+    /// let compose = ComposeYaml::new("
+    /// services:
+    ///   app: {}
+    ///   adminer:
+    ///     profiles: [debug, tools]
+    /// ").unwrap();
+    /// let adminer = compose.get_service("adminer").unwrap();
+    /// assert!(compose.is_service_active(adminer, &["tools".to_string()]));
+    /// assert!(compose.is_service_active(adminer, &["*".to_string()]));
+    /// assert!(!compose.is_service_active(adminer, &[]));
+    /// let app = compose.get_service("app").unwrap();
+    /// assert!(compose.is_service_active(app, &[]));
+    /// ```
+    pub fn is_service_active(&self, service: &Mapping, profiles: &[String]) -> bool {
+        let service_profiles = self.get_service_profiles(service);
+        service_profiles.is_empty()
+            || profiles
+                .iter()
+                .any(|p| p == "*" || service_profiles.contains(&p.as_str()))
+    }
+
+    /// Remove the services not enabled with the profiles passed, without
+    /// profiles only the services without the `profiles` attribute are kept.
+    /// Like [`ComposeYaml::filter_services`] does, the references to the
+    /// services removed and the top-level elements no longer used are removed.
+    pub fn filter_profiles(&mut self, profiles: &[String]) {
+        let services = self.get_services().unwrap_or(&EMPTY_MAP);
+        let active = services
+            .iter()
+            .filter(|(_, service)| {
+                service
+                    .as_mapping()
+                    .map(|s| self.is_service_active(s, profiles))
+                    .unwrap_or(true)
+            })
+            .flat_map(|(name, _)| name.as_str())
+            .map(String::from)
+            .collect::<Vec<_>>();
+        if active.len() < services.len() {
+            self.retain_services(&active);
+        }
+    }
+
     pub fn get_images(
         &self,
         filter_by_tag: Option<&str>,
         tag: Option<&ReplaceTag>,
-    ) -> Option<Vec<String>> {
-        let services = self.get_services()?;
-        let mut images = services
-            .values()
-            .flat_map(|v| v.as_mapping())
-            .flat_map(|s| s.get("image"))
-            .flat_map(|p| p.as_str())
-            .filter(|image| match filter_by_tag {
+    ) -> Result<Option<Vec<String>>, PoseError> {
+        let service_images = match self.get_service_images(filter_by_tag, tag)? {
+            Some(service_images) => service_images,
+            None => return Ok(None),
+        };
+        let mut images = service_images
+            .into_iter()
+            .map(|i| i.resolved)
+            .collect::<Vec<_>>();
+        images.sort();
+        images.dedup();
+        Ok(Some(images))
+    }
+
+    /// Get the image of each service, and the image resolved with the tag
+    /// passed if it exists locally or in the remote registry, otherwise
+    /// the resolved image is the same as the one set in the service.
+    pub fn get_service_images(
+        &self,
+        filter_by_tag: Option<&str>,
+        tag: Option<&ReplaceTag>,
+    ) -> Result<Option<Vec<ServiceImage>>, PoseError> {
+        let services = match self.get_services() {
+            Some(services) => services,
+            None => return Ok(None),
+        };
+        let service_images = services
+            .iter()
+            .flat_map(|(k, v)| {
+                let image = v.as_mapping()?.get("image")?.as_str()?;
+                Some((k.as_str().unwrap_or_default(), image))
+            })
+            .filter(|(_, image)| match filter_by_tag {
                 None => true,
-                Some(tag) => {
-                    let image_parts = image.split(':').collect::<Vec<_>>();
-                    let image_tag = if image_parts.len() > 1 {
-                        *image_parts.get(1).unwrap()
-                    } else {
-                        "latest"
-                    };
-                    tag == image_tag
-                }
+                Some(tag) => ImageRef::parse(image)
+                    .map(|image_ref| tag == image_ref.get_tag())
+                    .unwrap_or(false),
             })
             .collect::<Vec<_>>();
+        let mut images = service_images
+            .iter()
+            .map(|(_, image)| *image)
+            .collect::<Vec<_>>();
         images.sort();
         images.dedup();
-        if let Some(replace_tag) = tag {
-            let show_progress = matches!(replace_tag.verbosity, Verbosity::Verbose)
-                || matches!(replace_tag.progress_verbosity, Verbosity::Verbose);
-            let input = Arc::new(Mutex::new(
-                images
-                    .iter()
-                    .rev()
-                    .map(|e| e.to_string())
-                    .collect::<Vec<String>>(),
-            ));
-            let replace_arc = Arc::new(replace_tag.clone());
-            let mut updated_images: Vec<String> = Vec::with_capacity(images.len());
-            let (tx, rx): (Sender<String>, Receiver<String>) = mpsc::channel();
-            let mut thread_children = Vec::new();
-            let nthreads = max(1, min(images.len(), replace_tag.threads as usize));
-            if matches!(replace_tag.verbosity, Verbosity::Verbose) {
-                eprintln!(
-                    "{}: spawning {} threads to fetch remote info from {} images",
-                    "DEBUG".green(),
-                    nthreads,
-                    images.len()
-                )
-            }
-            for _ in 0..nthreads {
-                let input = Arc::clone(&input);
-                let replace = Arc::clone(&replace_arc);
-                let thread_tx = tx.clone();
-                let child = thread::spawn(move || {
-                    loop {
-                        let mut v = input.lock().unwrap();
-                        let last = v.pop(); // take one element out from the vec and free
-                        drop(v); // the vector lock so other threads can get it
-                        if let Some(image) = last {
-                            let image_parts = image.split(':').collect::<Vec<_>>();
-                            let image_name = *image_parts.first().unwrap();
-                            let remote_image =
-                                format!("{}:{}", image_name, replace.get_remote_tag());
-                            if replace
-                                .tag_filter
-                                .as_ref()
-                                .map(|r| (r.1, r.0.is_match(&image)))
-                                .map(|(affirmative_expr, is_match)| {
-                                    (affirmative_expr && is_match)
-                                        || (!affirmative_expr && !is_match)
-                                })
-                                .unwrap_or(true)
-                            {
-                                // check whether the image:<tag> exists or not locally
-                                match Self::has_image(&replace, &remote_image, show_progress) {
-                                    true => thread_tx.send(remote_image).unwrap(),
-                                    false => match replace.offline {
-                                        true => thread_tx.send(image).unwrap(),
-                                        // if not exists locally, check remote registry
-                                        false => match Self::has_manifest(
-                                            &replace,
-                                            &remote_image,
-                                            show_progress,
-                                        ) {
-                                            true => thread_tx.send(remote_image).unwrap(),
-                                            false => thread_tx.send(image).unwrap(),
-                                        },
-                                    },
-                                }
-                            } else {
-                                // skip the remote check and add it as it is into the list
-                                if show_progress {
-                                    eprintln!(
-                                        "{}: manifest for image {} ... {} ",
-                                        "DEBUG".green(),
-                                        image_name.yellow(),
-                                        "skipped".bright_black()
-                                    );
-                                }
-                                thread_tx.send(image.to_string()).unwrap();
-                            }
-                        } else {
-                            break; // The vector got empty, all elements were processed
-                        }
+        let resolved_images = match tag {
+            Some(replace_tag) => Self::resolve_images(&images, replace_tag)?,
+            None => HashMap::new(),
+        };
+        Ok(Some(
+            service_images
+                .into_iter()
+                .map(|(service, image)| ServiceImage {
+                    service: service.to_string(),
+                    image: image.to_string(),
+                    resolved: resolved_images
+                        .get(image)
+                        .cloned()
+                        .unwrap_or(image.to_string()),
+                })
+                .collect::<Vec<_>>(),
+        ))
+    }
+
+    /// Check in parallel whether the images exist with the tag passed
+    /// locally or in the remote registry, returning a map with the
+    /// image resolved for each image passed.
+    fn resolve_images(
+        images: &[&str],
+        replace_tag: &ReplaceTag,
+    ) -> Result<HashMap<String, String>, PoseError> {
+        let show_progress = matches!(replace_tag.verbosity, Verbosity::Verbose)
+            || matches!(replace_tag.progress_verbosity, Verbosity::Verbose);
+        let replace = replace_tag.clone();
+        let client = RegistryClient::from_docker_config(replace_tag.verbosity.clone());
+        let cache = replace_tag
+            .cache_dir
+            .as_ref()
+            .map(|dir| Arc::new(TagCache::load(dir, replace_tag.verbosity.clone())));
+        let thread_cache = cache.clone();
+        let resolved_images = process_in_parallel(
+            images,
+            replace_tag.threads,
+            &replace_tag.verbosity,
+            move |image| {
+                let cache = thread_cache.as_deref();
+                Self::resolve_image(&replace, &client, cache, image, show_progress)
+            },
+        );
+        if let Some(cache) = cache {
+            cache.save();
+        }
+        resolved_images
+    }
+
+    fn resolve_image(
+        replace: &ReplaceTag,
+        client: &RegistryClient,
+        cache: Option<&TagCache>,
+        image: String,
+        show_progress: bool,
+    ) -> Result<String, PoseError> {
+        let image_ref = match ImageRef::parse(&image) {
+            Ok(image_ref) => image_ref,
+            Err(e) => {
+                // e.g. images with variables not interpolated
+                if show_progress {
+                    eprintln!(
+                        "{}: {} ... {}",
+                        "DEBUG".green(),
+                        e,
+                        "skipped".bright_black()
+                    );
+                }
+                return Ok(image);
+            }
+        };
+        let image_name = image_ref.name();
+        if replace
+            .tag_filter
+            .as_ref()
+            .map(|r| (r.1, r.0.is_match(&image)))
+            .map(|(affirmative_expr, is_match)| {
+                (affirmative_expr && is_match) || (!affirmative_expr && !is_match)
+            })
+            .unwrap_or(true)
+        {
+            let tags = replace.get_remote_tags();
+            for (i, tag) in tags.iter().enumerate() {
+                let remote_ref = image_ref.with_tag(tag);
+                let remote_image = remote_ref.to_string();
+                // check whether the image:<tag> exists or not locally,
+                // and if not exists locally, check remote registry
+                if Self::has_image(replace, &remote_image, show_progress)?
+                    || (!replace.offline
+                        && Self::has_manifest(replace, client, cache, &remote_ref, show_progress)?)
+                {
+                    if show_progress && tags.len() > 1 {
+                        eprintln!(
+                            "{}: image {} resolved with tag {} (candidate {} of {})",
+                            "DEBUG".green(),
+                            image.yellow(),
+                            tag.green(),
+                            i + 1,
+                            tags.len()
+                        );
                     }
-                });
-                thread_children.push(child);
+                    return Ok(remote_image);
+                }
             }
-            for _ in 0..images.len() {
-                let out = rx.recv().unwrap();
-                updated_images.push(out);
+            if show_progress && tags.len() > 1 {
+                eprintln!(
+                    "{}: image {} ... {}",
+                    "DEBUG".green(),
+                    image.yellow(),
+                    "no tag candidate found".purple()
+                );
             }
-            for child in thread_children {
-                child.join().unwrap_or_else(|e| {
-                    eprintln!(
-                        "{}: child thread panicked while fetching remote images info: {:?}",
-                        "ERROR".red(),
-                        e
-                    );
-                });
+            Ok(image)
+        } else {
+            // skip the remote check and add it as it is into the list
+            if show_progress {
+                eprintln!(
+                    "{}: manifest for image {} ... {} ",
+                    "DEBUG".green(),
+                    image_name.yellow(),
+                    "skipped".bright_black()
+                );
             }
-            updated_images.sort();
-            return Some(updated_images);
+            Ok(image)
         }
-        Some(images.iter().map(|i| i.to_string()).collect::<Vec<_>>())
     }
 
     /// Returns whether the image exists locally, handling possible errors.
     /// When the image exists, means the image exists for the
     /// particular tag passed in the local registry.
-    fn has_image(replace_tag: &ReplaceTag, remote_image: &str, show_progress: bool) -> bool {
+    fn has_image(
+        replace_tag: &ReplaceTag,
+        remote_image: &str,
+        show_progress: bool,
+    ) -> Result<bool, PoseError> {
         let command = DockerCommand::new(replace_tag.verbosity.clone());
-        let inspect_output = command.get_image_inspect(remote_image).unwrap_or_else(|e| {
-            eprintln!(
-                "{}: fetching image manifest locally for {}: {}",
-                "ERROR".red(),
-                remote_image,
-                e
-            );
-            process::exit(151);
-        });
+        let inspect_output = match command.get_image_inspect(remote_image) {
+            Ok(output) => output,
+            // without the docker CLI only the remote registry is checked
+            Err(e) if e.kind() == io::ErrorKind::NotFound && !replace_tag.offline => {
+                if matches!(replace_tag.verbosity, Verbosity::Verbose) {
+                    eprintln!(
+                        "{}: {} not found, skipping local check of image {}",
+                        "DEBUG".green(),
+                        command.docker_bin,
+                        remote_image.yellow()
+                    );
+                }
+                return Ok(false);
+            }
+            Err(e) => {
+                return Err(PoseError::CommandCall(format!(
+                    "fetching image manifest locally for {}: {}",
+                    remote_image, e
+                )));
+            }
+        };
         if inspect_output.status.success() {
             if show_progress {
                 eprintln!(
@@ -245,9 +506,9 @@ impl ComposeYaml {
                     "found".green()
                 );
             }
-            true
+            Ok(true)
         } else {
-            let exit_code = command.exit_code(&inspect_output);
+            let exit_code = command.exit_code(&inspect_output)?;
             let stderr = String::from_utf8(inspect_output.stderr).unwrap();
             if stderr.to_lowercase().contains("no such image") {
                 if show_progress && replace_tag.offline {
@@ -258,15 +519,15 @@ impl ComposeYaml {
                         "not found".purple()
                     );
                 }
-                false
+                Ok(false)
             } else {
-                eprintln!(
-                    "{}: fetching local image manifest for {}: {}",
-                    "ERROR".red(),
-                    remote_image,
-                    stderr
-                );
-                process::exit(exit_code);
+                Err(PoseError::Command(
+                    format!(
+                        "fetching local image manifest for {}: {}",
+                        remote_image, stderr
+                    ),
+                    exit_code,
+                ))
             }
         }
     }
@@ -274,96 +535,249 @@ impl ComposeYaml {
     /// Returns whether the manifest exists, handling possible errors.
     /// When the manifest exists, means the image exists for the
     /// particular tag passed in the remote registry.
-    fn has_manifest(replace_tag: &ReplaceTag, remote_image: &str, show_progress: bool) -> bool {
-        let command = DockerCommand::new(replace_tag.verbosity.clone());
-        let inspect_output = command
-            .get_manifest_inspect(remote_image)
-            .unwrap_or_else(|e| {
-                eprintln!(
-                    "{}: fetching image manifest for {}: {}",
-                    "ERROR".red(),
-                    remote_image,
-                    e
-                );
-                process::exit(151);
-            });
-        if inspect_output.status.success() {
+    fn has_manifest(
+        replace_tag: &ReplaceTag,
+        client: &RegistryClient,
+        cache: Option<&TagCache>,
+        remote_image: &ImageRef,
+        show_progress: bool,
+    ) -> Result<bool, PoseError> {
+        let image_name = remote_image.to_string();
+        let cache_key = format!(
+            "{}:{}",
+            remote_image.canonical_name(),
+            remote_image.get_tag()
+        );
+        if let Some(found) = cache.and_then(|c| c.get(&cache_key)) {
             if show_progress {
                 eprintln!(
-                    "{}: manifest for image {} ... {} ",
+                    "{}: manifest for image {} ... {} {}",
                     "DEBUG".green(),
-                    remote_image.yellow(),
-                    "found".green()
+                    image_name.yellow(),
+                    match found {
+                        true => "found".green(),
+                        false => "not found".purple(),
+                    },
+                    "(cached)".bright_black()
                 );
             }
-            true
-        } else {
-            let exit_code = command.exit_code(&inspect_output);
-            let stderr = String::from_utf8(inspect_output.stderr).unwrap();
-            if stderr.to_lowercase().contains("no such manifest")
-                || (replace_tag.ignore_unauthorized && stderr.contains("unauthorized:"))
-            {
-                if show_progress {
-                    eprintln!(
-                        "{}: manifest for image {} ... {}",
-                        "DEBUG".green(),
-                        remote_image.yellow(),
-                        "not found".purple()
-                    );
-                }
-                false
-            } else {
-                eprintln!(
-                    "{}: fetching image manifest for {}: {}",
-                    "ERROR".red(),
-                    remote_image,
-                    stderr
-                );
-                process::exit(exit_code);
+            return Ok(found);
+        }
+        // unauthorized errors ignored are not cached, so the tag is
+        // checked again once the credentials are fixed
+        let (found, cacheable) = match client.has_manifest(remote_image) {
+            Ok(found) => (found, true),
+            Err(RegistryError::Unauthorized(_)) if replace_tag.ignore_unauthorized => {
+                (false, false)
             }
+            Err(e) => return Err(PoseError::Registry(image_name, e)),
+        };
+        if let Some(cache) = cache.filter(|_| cacheable) {
+            cache.insert(&cache_key, found);
         }
+        if show_progress {
+            match found {
+                true => eprintln!(
+                    "{}: manifest for image {} ... {} ",
+                    "DEBUG".green(),
+                    image_name.yellow(),
+                    "found".green()
+                ),
+                false => eprintln!(
+                    "{}: manifest for image {} ... {}",
+                    "DEBUG".green(),
+                    image_name.yellow(),
+                    "not found".purple()
+                ),
+            }
+        }
+        Ok(found)
     }
 
     /// Update all services' image attributes with the tag passed if the
     /// tag exists locally or in the remote registry, otherwise
     /// the image value is untouched.
-    pub fn update_images_tag(&mut self, replace_tag: &ReplaceTag) {
-        if let Some(images_with_remote) = self.get_images(None, Some(replace_tag)) {
-            let services_names = self
-                .get_root_element_names("services")
+    pub fn update_images_tag(&mut self, replace_tag: &ReplaceTag) -> Result<(), PoseError> {
+        if let Some(service_images) = self.get_service_images(None, Some(replace_tag))? {
+            let services_op = self
+                .map
+                .get_mut("services")
+                .and_then(|v| v.as_mapping_mut());
+            if let Some(services) = services_op {
+                for service_image in service_images {
+                    if service_image.resolved == service_image.image {
+                        continue;
+                    }
+                    let image_op = services
+                        .get_mut(service_image.service.as_str())
+                        .and_then(|serv| serv.get_mut("image"));
+                    if let Some(Value::String(image)) = image_op {
+                        image.replace_range(.., &service_image.resolved);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Update all services' image attributes with the immutable reference
+    /// `repo@sha256:...` of the image, looking for the digest first in the local
+    /// registry and then in the remote registry. The original image is kept in
+    /// the `pose.image` label of the service. Images which digest is not found,
+    /// or that already have a digest, are untouched.
+    pub fn pin_images_digest(&mut self, pin_digest: &PinDigest) -> Result<(), PoseError> {
+        if let Some(service_images) = self.get_service_images(None, None)? {
+            let mut images = service_images
                 .iter()
-                .map(|s| s.to_string())
+                .map(|s| s.image.as_str())
                 .collect::<Vec<_>>();
+            images.sort();
+            images.dedup();
+            let show_progress = matches!(pin_digest.verbosity, Verbosity::Verbose)
+                || matches!(pin_digest.progress_verbosity, Verbosity::Verbose);
+            let pin = pin_digest.clone();
+            let client = RegistryClient::from_docker_config(pin_digest.verbosity.clone());
+            let pinned_images = process_in_parallel(
+                &images,
+                pin_digest.threads,
+                &pin_digest.verbosity,
+                move |image| Self::pin_image(&pin, &client, image, show_progress),
+            )?;
             let services_op = self
                 .map
                 .get_mut("services")
                 .and_then(|v| v.as_mapping_mut());
             if let Some(services) = services_op {
-                for service_name in services_names {
-                    let service = services.entry(Value::String(service_name.to_string()));
-                    service.and_modify(|serv| {
-                        if let Some(image_value) = serv.get_mut("image") {
-                            let image = image_value
-                                .as_str()
-                                .map(|i| i.to_string())
-                                .unwrap_or_default();
-                            let image_name = image.split(':').next().unwrap_or_default();
-                            let remote_image_op = images_with_remote.iter().find(|i| {
-                                let remote_image_name = i.split(':').next().unwrap_or_default();
-                                image_name == remote_image_name
-                            });
-                            if let Some(remote_image) = remote_image_op {
-                                if remote_image != &image {
-                                    if let Value::String(string) = image_value {
-                                        string.replace_range(.., remote_image);
-                                    }
-                                }
-                            }
-                        }
-                    });
+                for service_image in service_images {
+                    let pinned = match pinned_images.get(&service_image.image) {
+                        Some(pinned) if *pinned != service_image.image => pinned,
+                        _ => continue,
+                    };
+                    if let Some(Value::Mapping(service)) =
+                        services.get_mut(service_image.service.as_str())
+                    {
+                        service.insert(Value::from("image"), Value::from(pinned.as_str()));
+                        add_label(service, "pose.image", &service_image.image);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn pin_image(
+        pin_digest: &PinDigest,
+        client: &RegistryClient,
+        image: String,
+        show_progress: bool,
+    ) -> Result<String, PoseError> {
+        let image_ref = match ImageRef::parse(&image) {
+            Ok(image_ref) if image_ref.digest.is_none() => image_ref,
+            Ok(_) => return Ok(image), // already pinned
+            Err(e) => {
+                // e.g. images with variables not interpolated
+                if show_progress {
+                    eprintln!(
+                        "{}: {} ... {}",
+                        "DEBUG".green(),
+                        e,
+                        "skipped".bright_black()
+                    );
                 }
+                return Ok(image);
+            }
+        };
+        let mut digest = Self::get_local_digest(pin_digest, &image, &image_ref, show_progress)?;
+        if digest.is_none() && !pin_digest.offline {
+            digest = Self::get_remote_digest(pin_digest, client, &image_ref, show_progress)?;
+        }
+        match digest {
+            Some(digest) => Ok(format!("{}@{}", image_ref.name(), digest)),
+            None => {
+                if !matches!(pin_digest.verbosity, Verbosity::Quiet) {
+                    eprintln!(
+                        "{}: digest for image {} not found, the image is not pinned",
+                        "WARN".yellow(),
+                        image.yellow()
+                    );
+                }
+                Ok(image)
+            }
+        }
+    }
+
+    /// Get the digest of the image from the local registry, only if the image
+    /// was pulled from a registry, images built locally don't have a digest.
+    fn get_local_digest(
+        pin_digest: &PinDigest,
+        image: &str,
+        image_ref: &ImageRef,
+        show_progress: bool,
+    ) -> Result<Option<String>, PoseError> {
+        let command = DockerCommand::new(pin_digest.verbosity.clone());
+        let inspect_output = command.get_image_inspect(image).map_err(|e| {
+            PoseError::CommandCall(format!(
+                "fetching image manifest locally for {}: {}",
+                image, e
+            ))
+        })?;
+        if !inspect_output.status.success() {
+            let exit_code = command.exit_code(&inspect_output)?;
+            let stderr = String::from_utf8_lossy(&inspect_output.stderr);
+            if !stderr.to_lowercase().contains("no such image") {
+                return Err(PoseError::Command(
+                    format!("fetching local image manifest for {}: {}", image, stderr),
+                    exit_code,
+                ));
+            }
+            return Ok(None);
+        }
+        let stdout = String::from_utf8_lossy(&inspect_output.stdout);
+        let digest = get_repo_digest(&stdout, image_ref);
+        if show_progress {
+            if let Some(digest) = &digest {
+                eprintln!(
+                    "{}: digest for image {} ... {} ",
+                    "DEBUG".green(),
+                    image.yellow(),
+                    digest.green()
+                );
             }
         }
+        Ok(digest)
+    }
+
+    /// Get the digest of the image from the remote registry, for multi-platform
+    /// images the digest of the manifest list is returned, like the local digest.
+    fn get_remote_digest(
+        pin_digest: &PinDigest,
+        client: &RegistryClient,
+        image_ref: &ImageRef,
+        show_progress: bool,
+    ) -> Result<Option<String>, PoseError> {
+        let image = image_ref.to_string();
+        let digest = match client.get_digest(image_ref) {
+            Ok(digest) => digest,
+            Err(RegistryError::Unauthorized(_)) if pin_digest.ignore_unauthorized => None,
+            Err(e) => return Err(PoseError::Registry(image, e)),
+        };
+        if show_progress {
+            match &digest {
+                Some(digest) => eprintln!(
+                    "{}: digest for image {} ... {} ",
+                    "DEBUG".green(),
+                    image.yellow(),
+                    digest.green()
+                ),
+                None => eprintln!(
+                    "{}: digest for image {} ... {}",
+                    "DEBUG".green(),
+                    image.yellow(),
+                    "not found".purple()
+                ),
+            }
+        }
+        Ok(digest)
     }
 
     pub fn get_service(&self, service_name: &str) -> Option<&Mapping> {
@@ -373,17 +787,22 @@ impl ComposeYaml {
     }
 
     pub fn get_service_envs(&self, service: &Mapping) -> Option<Vec<String>> {
+        let envs = self.get_service_env_vars(service)?;
+        Some(envs.iter().map(|env| env.to_string()).collect::<Vec<_>>())
+    }
+
+    /// Get the variables from the `environment` attribute of the service.
+    pub fn get_service_env_vars(&self, service: &Mapping) -> Option<Vec<EnvVar>> {
         let envs = service.get("environment")?;
         match envs.as_sequence() {
             Some(seq) => Some(
                 seq.iter()
                     .map(|v| {
                         let val = v.as_str().unwrap_or("");
-                        if ENV_NAME_REGEX.captures(val).is_some() {
+                        match val.split_once('=') {
+                            Some((key, value)) => EnvVar::new(key, Some(value), "environment"),
                             // Env variable without a value or "=" at the end
-                            format!("{val}=")
-                        } else {
-                            String::from(val)
+                            None => EnvVar::new(val, None, "environment"),
                         }
                     })
                     .collect::<Vec<_>>(),
@@ -394,20 +813,31 @@ impl ComposeYaml {
                     .into_iter()
                     .map(|(k, v)| {
                         let env = k.as_str().unwrap_or("".as_ref());
-                        let val = to_string(v).unwrap_or("".to_string());
-                        let val = val.trim_end();
-                        if val.contains(' ') {
-                            if val.contains('"') {
-                                format!("{env}='{val}'")
+                        let yaml_val = to_string(v).unwrap_or("".to_string());
+                        let yaml_val = yaml_val.trim_end();
+                        let val = match v {
+                            Value::Null => None,
+                            Value::String(s) => Some(s.to_string()),
+                            Value::Number(n) => Some(n.to_string()),
+                            Value::Bool(b) => Some(b.to_string()),
+                            _ => Some(yaml_val.to_string()),
+                        };
+                        let formatted = if yaml_val.contains(' ') {
+                            if yaml_val.contains('"') {
+                                format!("'{yaml_val}'")
                             } else {
-                                format!("{env}=\"{val}\"")
+                                format!("\"{yaml_val}\"")
                             }
-                        } else if QUOTED_NUM_REGEX.captures(val).is_some() {
+                        } else if QUOTED_NUM_REGEX.captures(yaml_val).is_some() {
                             // remove unnecessary quotes
-                            let val = &val[1..val.len() - 1];
-                            format!("{env}={val}")
+                            yaml_val[1..yaml_val.len() - 1].to_string()
                         } else {
-                            format!("{env}={val}")
+                            yaml_val.to_string()
+                        };
+                        EnvVar {
+                            formatted: Some(formatted)
+                                .filter(|f| f != val.as_deref().unwrap_or_default()),
+                            ..EnvVar::new(env, val.as_deref(), "environment")
                         }
                     })
                     .collect::<Vec<_>>(),
@@ -415,6 +845,73 @@ impl ComposeYaml {
         }
     }
 
+    /// Get the files set in the `env_file` attribute of the service,
+    /// and whether each file is required or not.
+    pub fn get_service_env_files(&self, service: &Mapping) -> Vec<(String, bool)> {
+        match service.get("env_file") {
+            Some(Value::String(path)) => vec![(path.to_string(), true)],
+            Some(Value::Sequence(seq)) => seq
+                .iter()
+                .flat_map(|el| match el {
+                    Value::String(path) => Some((path.to_string(), true)),
+                    Value::Mapping(map) => map.get("path").and_then(|p| p.as_str()).map(|p| {
+                        let required = map.get("required").and_then(|r| r.as_bool());
+                        (p.to_string(), required.unwrap_or(true))
+                    }),
+                    _ => None,
+                })
+                .collect::<Vec<_>>(),
+            _ => Vec::default(),
+        }
+    }
+
+    /// Get the variables of the service merging the ones from the
+    /// `env_file` files with the ones from the `environment` attribute,
+    /// following the Compose precedence rules: the `environment` attribute
+    /// takes precedence over the files, and each file over the previous ones.
+    /// The files are resolved relative to `project_dir`.
+    pub fn get_service_env_vars_with_files(
+        &self,
+        service: &Mapping,
+        project_dir: &Path,
+    ) -> Result<Vec<EnvVar>, PoseError> {
+        let mut envs: Vec<EnvVar> = Vec::new();
+        let mut add_env = |env: EnvVar| match envs.iter_mut().find(|e| e.key == env.key) {
+            // variables without a value don't replace the value from the env files
+            Some(_) if env.value.is_none() => {}
+            Some(e) => *e = env,
+            None => envs.push(env),
+        };
+        for (path, required) in self.get_service_env_files(service) {
+            let file_path = project_dir.join(&path);
+            if !file_path.is_file() {
+                if required {
+                    return Err(PoseError::ReadFile(format!(
+                        "env file {} not found",
+                        file_path.display()
+                    )));
+                }
+                continue;
+            }
+            for (key, value) in read_dotenv(&file_path).map_err(PoseError::ReadFile)? {
+                // variables without a value take it from the environment, if exists
+                let value = value.or_else(|| env::var(&key).ok());
+                if value.is_some() {
+                    add_env(EnvVar::new(&key, value.as_deref(), &path));
+                }
+            }
+        }
+        for env in self.get_service_env_vars(service).unwrap_or_default() {
+            // variables without a value take it from the environment, if exists
+            let env = match (&env.value, env::var(&env.key)) {
+                (None, Ok(value)) => EnvVar::new(&env.key, Some(&value), &env.source),
+                _ => env,
+            };
+            add_env(env);
+        }
+        Ok(envs)
+    }
+
     pub fn get_service_depends_on(&self, service: &Mapping) -> Option<Vec<String>> {
         let depends = service.get("depends_on")?;
         match depends.as_sequence() {
@@ -437,6 +934,480 @@ impl ComposeYaml {
             ),
         }
     }
+
+    /// Get the networks the service is connected to with the `networks` attribute.
+    pub fn get_service_networks(&self, service: &Mapping) -> Vec<String> {
+        match service.get("networks") {
+            Some(Value::Sequence(seq)) => seq
+                .iter()
+                .flat_map(|n| n.as_str())
+                .map(String::from)
+                .collect::<Vec<_>>(),
+            Some(Value::Mapping(map)) => map
+                .keys()
+                .flat_map(|n| n.as_str())
+                .map(String::from)
+                .collect::<Vec<_>>(),
+            _ => Vec::default(),
+        }
+    }
+
+    /// Get the named volumes mounted by the service, the bind
+    /// mounts and the anonymous volumes are not included.
+    pub fn get_service_named_volumes(&self, service: &Mapping) -> Vec<String> {
+        self.get_service_mounts(service)
+            .into_iter()
+            .filter(|m| m.mount_type == MountType::Volume)
+            .flat_map(|m| m.source)
+            .filter(|source| !source.starts_with('$'))
+            .collect::<Vec<_>>()
+    }
+
+    /// Get the volumes mounted by the service, including the `tmpfs` attribute.
+    /// The volumes that can't be parsed are skipped, see
+    /// [`ComposeYaml::get_invalid_mounts`] to get them.
+    pub fn get_service_mounts(&self, service: &Mapping) -> Vec<Mount> {
+        let volumes = get_service_volumes(service).flat_map(|volume| volume.ok());
+        let tmpfs = match service.get("tmpfs") {
+            Some(Value::String(tmpfs)) => vec![Mount::from_tmpfs(tmpfs)],
+            Some(Value::Sequence(seq)) => seq
+                .iter()
+                .flat_map(|t| t.as_str())
+                .map(Mount::from_tmpfs)
+                .collect::<Vec<_>>(),
+            _ => Vec::default(),
+        };
+        volumes.chain(tmpfs).collect::<Vec<_>>()
+    }
+
+    /// Get the volumes mounted by all the services.
+    pub fn get_mounts(&self) -> Vec<ServiceMount> {
+        self.get_services()
+            .unwrap_or(&EMPTY_MAP)
+            .iter()
+            .flat_map(|(name, service)| Some((name.as_str()?, service.as_mapping()?)))
+            .flat_map(|(name, service)| {
+                self.get_service_mounts(service)
+                    .into_iter()
+                    .map(|mount| ServiceMount {
+                        mount,
+                        service: name.to_string(),
+                    })
+            })
+            .collect::<Vec<_>>()
+    }
+
+    /// Get the volumes of all the services that can't be parsed, with the
+    /// name of the service and the error, e.g. with a type not supported.
+    ///
+    /// ```
+    /// use docker_pose::ComposeYaml;
+    ///
+    /// let compose = ComposeYaml::new("
+    /// services:
+    ///   app:
+    ///     volumes:
+    ///       - data:/data
+    ///       - {type: unknown, target: /other}
+    /// ").unwrap();
+    /// assert_eq!(
+    ///     compose.get_invalid_mounts(),
+    ///     vec![("app".to_string(), "invalid volume type 'unknown'".to_string())]
+    /// );
+    /// ```
+    pub fn get_invalid_mounts(&self) -> Vec<(String, String)> {
+        self.get_services()
+            .unwrap_or(&EMPTY_MAP)
+            .iter()
+            .flat_map(|(name, service)| Some((name.as_str()?, service.as_mapping()?)))
+            .flat_map(|(name, service)| {
+                get_service_volumes(service)
+                    .flat_map(|volume| volume.err())
+                    .map(|e| (name.to_string(), e))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>()
+    }
+
+    /// Get the names of the secrets used by the service.
+    pub fn get_service_secrets(&self, service: &Mapping) -> Vec<String> {
+        get_sources(service, "secrets")
+    }
+
+    /// Get the names of the configs used by the service.
+    pub fn get_service_configs(&self, service: &Mapping) -> Vec<String> {
+        get_sources(service, "configs")
+    }
+
+    /// Get the ports of all the services, the ports that can't
+    /// be parsed, e.g. with variables not interpolated, are skipped.
+    pub fn get_ports(&self) -> Vec<ServicePort> {
+        let mut ports = Vec::new();
+        for (name, service) in self.get_services().unwrap_or(&EMPTY_MAP) {
+            let name = name.as_str().unwrap_or_default();
+            let service_ports = service.get("ports").and_then(|p| p.as_sequence());
+            for (index, port) in service_ports.into_iter().flatten().enumerate() {
+                if let Ok(port) = PortMapping::from_value(port) {
+                    ports.push(ServicePort {
+                        port,
+                        service: name.to_string(),
+                        index,
+                    });
+                }
+            }
+        }
+        ports
+    }
+
+    /// Get the services referenced by the service: the ones in `depends_on`,
+    /// `links` and `volumes_from`, and the one in `network_mode: service:NAME`.
+    ///
+    /// ```
+    /// use docker_pose::ComposeYaml;
+    ///
+    /// let compose = ComposeYaml::new("
+    /// services:
+    ///   api:
+    ///     depends_on: [db]
+    ///     network_mode: service:vpn
+    ///     volumes_from: [data, 'service:logs:ro', 'container:legacy']
+    ///     links: ['cache:redis']
+    /// ").unwrap();
+    /// let api = compose.get_service("api").unwrap();
+    /// assert_eq!(
+    ///     compose.get_service_references(api),
+    ///     vec!["db", "vpn", "data", "logs", "cache"]
+    /// );
+    /// ```
+    pub fn get_service_references(&self, service: &Mapping) -> Vec<String> {
+        let mut references = self.get_service_depends_on(service).unwrap_or_default();
+        if let Some(name) = service
+            .get("network_mode")
+            .and_then(|n| n.as_str())
+            .and_then(network_mode_service)
+        {
+            references.push(name.to_string());
+        }
+        for (attribute, get_name) in [
+            (
+                "volumes_from",
+                volumes_from_service as fn(&str) -> Option<&str>,
+            ),
+            ("links", link_service),
+        ] {
+            references.extend(
+                service
+                    .get(attribute)
+                    .and_then(|v| v.as_sequence())
+                    .into_iter()
+                    .flatten()
+                    .flat_map(|el| el.as_str())
+                    .flat_map(get_name)
+                    .map(String::from),
+            );
+        }
+        references
+    }
+
+    /// Keep only the services passed, and the services they reference with
+    /// `with_deps` (see [`ComposeYaml::get_service_references`]), removing the
+    /// top-level volumes, networks, configs and secrets that are no longer used,
+    /// so the model is still a valid compose file.
+    /// Without `with_deps` the references to the services removed are dropped as well.
+    ///
+    /// ```
+    /// use docker_pose::ComposeYaml;
+    ///
+    /// let mut compose = ComposeYaml::new("
+    /// services:
+    ///   app:
+    ///     depends_on: [db]
+    ///   db:
+    ///     volumes: [data:/data]
+    ///   web: {}
+    /// volumes:
+    ///   data:
+    /// ").unwrap();
+    /// compose.filter_services(&["app".to_string()], true).unwrap();
+    /// assert_eq!(compose.get_root_element_names("services"), vec!["app", "db"]);
+    /// assert_eq!(compose.get_root_element_names("volumes"), vec!["data"]);
+    /// ```
+    pub fn filter_services(
+        &mut self,
+        services: &[String],
+        with_deps: bool,
+    ) -> Result<(), PoseError> {
+        let mut selected: Vec<String> = Vec::new();
+        let mut pending = services.to_vec();
+        while let Some(name) = pending.pop() {
+            if selected.contains(&name) {
+                continue;
+            }
+            let service = self
+                .get_service(&name)
+                .ok_or_else(|| PoseError::ServiceNotFound(name.clone()))?;
+            if with_deps {
+                pending.extend(self.get_service_references(service));
+            }
+            selected.push(name);
+        }
+        self.retain_services(&selected);
+        Ok(())
+    }
+
+    /// Keep only the services passed, removing the references to the other
+    /// services and the top-level elements no longer used.
+    fn retain_services(&mut self, selected: &[String]) {
+        if let Some(Value::Mapping(services)) = self.map.get_mut("services") {
+            services.retain(|name, _| {
+                name.as_str()
+                    .map(|n| selected.iter().any(|s| s == n))
+                    .unwrap_or(false)
+            });
+            for service in services.values_mut().flat_map(|s| s.as_mapping_mut()) {
+                remove_references(service, selected);
+            }
+        }
+        let services = self
+            .get_services()
+            .unwrap_or(&EMPTY_MAP)
+            .values()
+            .flat_map(|s| s.as_mapping())
+            .collect::<Vec<_>>();
+        let build = |service: &Mapping| {
+            service
+                .get("build")
+                .and_then(|b| b.as_mapping())
+                .cloned()
+                .unwrap_or_default()
+        };
+        let mut used: HashMap<&str, Vec<String>> = HashMap::new();
+        for service in services {
+            used.entry("volumes")
+                .or_default()
+                .extend(self.get_service_named_volumes(service));
+            used.entry("networks")
+                .or_default()
+                .extend(self.get_service_networks(service));
+            used.entry("configs")
+                .or_default()
+                .extend(self.get_service_configs(service));
+            // secrets can be used to build the image as well
+            used.entry("secrets").or_default().extend(
+                self.get_service_secrets(service)
+                    .into_iter()
+                    .chain(get_sources(&build(service), "secrets")),
+            );
+        }
+        // the default network is used by all the services without networks
+        used.entry("networks")
+            .or_default()
+            .push("default".to_string());
+        for element in ["volumes", "networks", "configs", "secrets"] {
+            let used = used.remove(element).unwrap_or_default();
+            if let Some(Value::Mapping(elements)) = self.map.get_mut(element) {
+                elements.retain(|name, _| {
+                    name.as_str()
+                        .map(|n| used.iter().any(|u| u == n))
+                        .unwrap_or(false)
+                });
+                if elements.is_empty() {
+                    self.map.remove(element);
+                }
+            }
+        }
+    }
+}
+
+/// Remove the references to the services that are not in the list passed,
+/// from `depends_on`, `links`, `volumes_from` and `network_mode`.
+fn remove_references(service: &mut Mapping, services: &[String]) {
+    let keep = |name: Option<&str>| name.map(|n| services.iter().any(|s| s == n));
+    let is_empty = match service.get_mut("depends_on") {
+        Some(Value::Sequence(depends_on)) => {
+            depends_on.retain(|name| keep(name.as_str()).unwrap_or(false));
+            depends_on.is_empty()
+        }
+        Some(Value::Mapping(depends_on)) => {
+            depends_on.retain(|name, _| keep(name.as_str()).unwrap_or(false));
+            depends_on.is_empty()
+        }
+        _ => false,
+    };
+    if is_empty {
+        service.remove("depends_on");
+    }
+    for (attribute, get_name) in [
+        (
+            "volumes_from",
+            volumes_from_service as fn(&str) -> Option<&str>,
+        ),
+        ("links", link_service),
+    ] {
+        if let Some(Value::Sequence(references)) = service.get_mut(attribute) {
+            // the containers referenced in `volumes_from` are kept
+            references.retain(|el| {
+                el.as_str()
+                    .map(|r| keep(get_name(r)).unwrap_or(true))
+                    .unwrap_or(false)
+            });
+            if references.is_empty() {
+                service.remove(attribute);
+            }
+        }
+    }
+    let network_service = service
+        .get("network_mode")
+        .and_then(|n| n.as_str())
+        .and_then(network_mode_service);
+    if keep(network_service) == Some(false) {
+        service.remove("network_mode");
+    }
+}
+
+/// Service of `network_mode: service:NAME`.
+fn network_mode_service(network_mode: &str) -> Option<&str> {
+    network_mode.strip_prefix("service:")
+}
+
+/// Service of an entry of `volumes_from` like `NAME`, `NAME:ro` or
+/// `service:NAME:ro`, `None` for containers (`container:NAME`).
+fn volumes_from_service(volumes_from: &str) -> Option<&str> {
+    match volumes_from.split_once(':') {
+        Some(("container", _)) => None,
+        Some(("service", rest)) => rest.split(':').next(),
+        _ => volumes_from.split(':').next(),
+    }
+}
+
+/// Service of an entry of `links` like `NAME` or `NAME:ALIAS`.
+fn link_service(link: &str) -> Option<&str> {
+    link.split(':').next()
+}
+
+/// Parse the volumes of the `volumes` attribute of the service.
+fn get_service_volumes(service: &Mapping) -> impl Iterator<Item = Result<Mount, String>> + '_ {
+    service
+        .get("volumes")
+        .and_then(|v| v.as_sequence())
+        .into_iter()
+        .flatten()
+        .map(Mount::from_value)
+}
+
+/// Get the names of the secrets or the configs used by the service,
+/// declared in the short syntax or with the `source` attribute.
+fn get_sources(service: &Mapping, attribute: &str) -> Vec<String> {
+    service
+        .get(attribute)
+        .and_then(|v| v.as_sequence())
+        .unwrap_or(&Vec::default())
+        .iter()
+        .flat_map(|el| match el {
+            Value::String(name) => Some(name.to_string()),
+            Value::Mapping(map) => map.get("source").and_then(|s| s.as_str()).map(String::from),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+}
+
+/// Add the label to the service, respecting the syntax used
+/// in the `labels` attribute if it's already set.
+fn add_label(service: &mut Mapping, key: &str, value: &str) {
+    match service.get_mut("labels") {
+        Some(Value::Sequence(labels)) => {
+            labels.push(Value::from(format!("{}={}", key, value)));
+        }
+        Some(Value::Mapping(labels)) => {
+            labels.insert(Value::from(key), Value::from(value));
+        }
+        _ => {
+            let mut labels = Mapping::new();
+            labels.insert(Value::from(key), Value::from(value));
+            service.insert(Value::from("labels"), Value::Mapping(labels));
+        }
+    }
+}
+
+/// Run the task passed in parallel over the items, using at most `threads`
+/// threads, returning a map with the result of the task for each item,
+/// or the first error returned by the task.
+fn process_in_parallel<F>(
+    items: &[&str],
+    threads: u8,
+    verbosity: &Verbosity,
+    task: F,
+) -> Result<HashMap<String, String>, PoseError>
+where
+    F: Fn(String) -> Result<String, PoseError> + Send + Sync + 'static,
+{
+    let input = Arc::new(Mutex::new(
+        items
+            .iter()
+            .rev()
+            .map(|e| e.to_string())
+            .collect::<Vec<String>>(),
+    ));
+    let task_arc = Arc::new(task);
+    let mut results: HashMap<String, String> = HashMap::with_capacity(items.len());
+    // each message sent is the item and the result of the task
+    let (tx, rx): (Sender<ImageResult>, Receiver<ImageResult>) = mpsc::channel();
+    let mut thread_children = Vec::new();
+    let nthreads = max(1, min(items.len(), threads as usize));
+    if matches!(verbosity, Verbosity::Verbose) {
+        eprintln!(
+            "{}: spawning {} threads to fetch remote info from {} images",
+            "DEBUG".green(),
+            nthreads,
+            items.len()
+        )
+    }
+    for _ in 0..nthreads {
+        let input = Arc::clone(&input);
+        let task = Arc::clone(&task_arc);
+        let thread_tx = tx.clone();
+        let child = thread::spawn(move || {
+            loop {
+                let mut v = input.lock().unwrap();
+                let last = v.pop(); // take one element out from the vec and free
+                drop(v); // the vector lock so other threads can get it
+                if let Some(item) = last {
+                    let result = task(item.clone());
+                    thread_tx.send((item, result)).unwrap();
+                } else {
+                    break; // The vector got empty, all elements were processed
+                }
+            }
+        });
+        thread_children.push(child);
+    }
+    drop(tx); // only the threads' senders are kept, so the loop ends when all finish
+    let mut error = None;
+    for (item, result) in rx {
+        match result {
+            Ok(result) => {
+                results.insert(item, result);
+            }
+            Err(e) => {
+                // stop processing the items left
+                input.lock().unwrap().clear();
+                error.get_or_insert(e);
+            }
+        }
+    }
+    for child in thread_children {
+        child.join().unwrap_or_else(|e| {
+            eprintln!(
+                "{}: child thread panicked while fetching remote images info: {:?}",
+                "ERROR".red(),
+                e
+            );
+        });
+    }
+    match error {
+        Some(e) => Err(e),
+        None => Ok(results),
+    }
 }
 
 // where to look for the compose file when the user
@@ -451,29 +1422,28 @@ static COMPOSE_PATHS: [&str; 4] = [
 pub fn get_compose_filename(
     filename: Option<&str>,
     verbosity: Verbosity,
-) -> Result<String, String> {
+) -> Result<String, PoseError> {
     match filename {
         Some(name) => {
             if Path::new(&name).exists() {
                 Ok(String::from(name))
             } else {
-                Err(format!(
-                    "{}: {}: no such file or directory",
-                    "ERROR".red(),
+                Err(PoseError::NotFound(format!(
+                    "{}: no such file or directory",
                     name
-                ))
+                )))
             }
         }
         None => {
             let files = COMPOSE_PATHS.into_iter().filter(|f| Path::new(f).exists());
             let files_count = files.clone().count();
             match files_count {
-                0 => Err(format!(
+                0 => Err(PoseError::Other(format!(
                     "Can't find a suitable configuration file in this directory.\n\
                     Are you in the right directory?\n\n\
                     Supported filenames: {}",
                     COMPOSE_PATHS.into_iter().collect::<Vec<&str>>().join(", ")
-                )),
+                ))),
                 1 => {
                     let filename_0 = files.map(String::from).next().unwrap();
                     if matches!(verbosity, Verbosity::Verbose) {
@@ -551,6 +1521,42 @@ pub fn string_script(s: &str) -> Result<(String, String), &'static str> {
     Err("wrong expression")
 }
 
+/// Parser of fallbacks of the `get` command: a script in the form
+/// of "text1:text2", or a value for the placeholders of the URL template.
+/// Branch names cannot have the symbol `:`.
+///
+/// ```
+/// use docker_pose::{fallback, Fallback};
+///
+/// assert_eq!(fallback("main"), Ok(Fallback::Value("main".to_string())));
+/// assert_eq!(
+///     fallback("feature-a:main"),
+///     Ok(Fallback::Script("feature-a".to_string(), "main".to_string()))
+/// );
+/// assert_eq!(fallback(""), Err("must be at least 1 character long"));
+/// ```
+pub fn fallback(s: &str) -> Result<Fallback, &'static str> {
+    match s.contains(':') {
+        true => string_script(s).map(|(left, right)| Fallback::Script(left, right)),
+        false => string_no_empty(s).map(Fallback::Value),
+    }
+}
+
+/// Parser of SHA-256 checksums in hexadecimal, returned in lowercase.
+///
+/// ```
+/// use docker_pose::sha256;
+///
+/// assert_eq!(sha256(&"C0FF".repeat(16)), Ok("c0ff".repeat(16)));
+/// assert_eq!(sha256("c0ff"), Err("must be 64 hexadecimal characters long"));
+/// ```
+pub fn sha256(s: &str) -> Result<String, &'static str> {
+    match is_sha256(s) {
+        true => Ok(s.to_lowercase()),
+        false => Err("must be 64 hexadecimal characters long"),
+    }
+}
+
 /// Parser of headers in the form of "Name: value".
 /// Return a tuple of 2 strings: ("text1, "text2").
 ///
@@ -0,0 +1,237 @@
+/// Parser of the ports published by the services, in the short syntax
+/// `[HOST_IP:][HOST_PORT:]CONTAINER_PORT[/PROTOCOL]` or the long syntax.
+use serde::{Serialize, Serializer};
+use serde_yaml::Value;
+use std::fmt;
+
+/// A range of ports, a single port if `start` and `end` are the same.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PortRange {
+    pub start: u16,
+    pub end: u16,
+}
+
+impl PortRange {
+    pub fn new(start: u16, end: u16) -> Self {
+        Self { start, end }
+    }
+
+    fn parse(range: &str) -> Result<Self, String> {
+        let port = |p: &str| {
+            p.trim()
+                .parse::<u16>()
+                .map_err(|_| format!("invalid port '{}'", p))
+        };
+        match range.split_once('-') {
+            Some((start, end)) => {
+                let (start, end) = (port(start)?, port(end)?);
+                if start > end {
+                    return Err(format!("invalid port range '{}'", range));
+                }
+                Ok(Self::new(start, end))
+            }
+            None => port(range).map(|p| Self::new(p, p)),
+        }
+    }
+
+    pub fn overlaps(&self, other: &PortRange) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+}
+
+impl Serialize for PortRange {
+    /// Serialized as in the short syntax, e.g. "80" or "80-81".
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl fmt::Display for PortRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.start == self.end {
+            true => write!(f, "{}", self.start),
+            false => write!(f, "{}-{}", self.start, self.end),
+        }
+    }
+}
+
+/// A port of a service, and the port of the host where it's published.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct PortMapping {
+    /// IP of the host where the port is published, `None` for all the interfaces
+    pub host_ip: Option<String>,
+    /// port published in the host, `None` if the port isn't published or
+    /// a random port is assigned by docker
+    pub published: Option<PortRange>,
+    /// port of the container
+    pub target: PortRange,
+    pub protocol: String,
+}
+
+impl PortMapping {
+    /// Parse a port in the short syntax.
+    ///
+    /// ```
+    /// use docker_pose::{PortMapping, PortRange};
+    ///
+    /// let port = PortMapping::parse("127.0.0.1:8080:80/udp").unwrap();
+    /// assert_eq!(port.host_ip, Some("127.0.0.1".to_string()));
+    /// assert_eq!(port.published, Some(PortRange::new(8080, 8080)));
+    /// assert_eq!(port.target, PortRange::new(80, 80));
+    /// assert_eq!(port.protocol, "udp");
+    ///
+    /// let port = PortMapping::parse("[::1]:9000-9001:9000-9001").unwrap();
+    /// assert_eq!(port.host_ip, Some("::1".to_string()));
+    /// assert_eq!(port.published, Some(PortRange::new(9000, 9001)));
+    ///
+    /// let port = PortMapping::parse("3000").unwrap();
+    /// assert_eq!(port.published, None);
+    /// assert_eq!(port.protocol, "tcp");
+    ///
+    /// assert!(PortMapping::parse("${PORT}:80").is_err());
+    /// ```
+    pub fn parse(port: &str) -> Result<Self, String> {
+        let invalid = |e: String| format!("invalid port '{}': {}", port, e);
+        let (mapping, protocol) = match port.rsplit_once('/') {
+            Some((mapping, protocol)) => (mapping, protocol.to_string()),
+            None => (port, "tcp".to_string()),
+        };
+        // IPv6 addresses are enclosed in brackets
+        let (host_ip, mapping) = match mapping.strip_prefix('[') {
+            Some(rest) => match rest.split_once("]:") {
+                Some((ip, rest)) => (Some(ip.to_string()), rest),
+                None => return Err(invalid("wrong IPv6 address".to_string())),
+            },
+            None => (None, mapping),
+        };
+        let parts = mapping.split(':').collect::<Vec<_>>();
+        let (host_ip, published, target) = match parts[..] {
+            [target] => (host_ip, None, target),
+            [published, target] => (host_ip, Some(published), target),
+            [ip, published, target] if host_ip.is_none() => {
+                (Some(ip.to_string()), Some(published), target)
+            }
+            _ => return Err(invalid("too many ':'".to_string())),
+        };
+        Ok(Self {
+            host_ip: host_ip.filter(|ip| !ip.is_empty()),
+            published: match published {
+                Some(p) if !p.is_empty() => Some(PortRange::parse(p).map_err(invalid)?),
+                _ => None,
+            },
+            target: PortRange::parse(target).map_err(invalid)?,
+            protocol,
+        })
+    }
+
+    /// Parse a port from the `ports` attribute of a service,
+    /// in the short or in the long syntax.
+    pub fn from_value(value: &Value) -> Result<Self, String> {
+        match value {
+            Value::String(port) => Self::parse(port),
+            Value::Number(port) => Self::parse(&port.to_string()),
+            Value::Mapping(port) => {
+                let field = |name: &str| match port.get(name) {
+                    Some(Value::String(s)) => Some(s.to_string()),
+                    Some(Value::Number(n)) => Some(n.to_string()),
+                    _ => None,
+                };
+                let target = field("target").ok_or("port without target")?;
+                Ok(Self {
+                    host_ip: field("host_ip"),
+                    published: match field("published") {
+                        Some(p) => Some(PortRange::parse(&p)?),
+                        None => None,
+                    },
+                    target: PortRange::parse(&target)?,
+                    protocol: field("protocol").unwrap_or("tcp".to_string()),
+                })
+            }
+            _ => Err("invalid port definition".to_string()),
+        }
+    }
+
+    /// Whether both ports are published in the same port of the host.
+    ///
+    /// ```
+    /// use docker_pose::PortMapping;
+    ///
+    /// let port = PortMapping::parse("8080:80").unwrap();
+    /// assert!(port.collides_with(&PortMapping::parse("127.0.0.1:8080:8080").unwrap()));
+    /// assert!(port.collides_with(&PortMapping::parse("8000-8100:8000-8100").unwrap()));
+    /// assert!(!port.collides_with(&PortMapping::parse("8080:80/udp").unwrap()));
+    /// assert!(!port.collides_with(&PortMapping::parse("80").unwrap()));
+    /// ```
+    pub fn collides_with(&self, other: &PortMapping) -> bool {
+        let any_ip = |ip: &Option<String>| match ip.as_deref() {
+            None | Some("0.0.0.0") | Some("::") => true,
+            Some(_) => false,
+        };
+        match (&self.published, &other.published) {
+            (Some(published), Some(other_published)) => {
+                self.protocol == other.protocol
+                    && published.overlaps(other_published)
+                    && (any_ip(&self.host_ip)
+                        || any_ip(&other.host_ip)
+                        || self.host_ip == other.host_ip)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for PortMapping {
+    /// Format the port in the short syntax.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.host_ip.as_deref() {
+            Some(ip) if ip.contains(':') => write!(f, "[{}]:", ip)?,
+            Some(ip) => write!(f, "{}:", ip)?,
+            None => {}
+        }
+        if let Some(published) = &self.published {
+            write!(f, "{}:", published)?;
+        } else if self.host_ip.is_some() {
+            write!(f, ":")?;
+        }
+        write!(f, "{}/{}", self.target, self.protocol)
+    }
+}
+
+/// A port of a service, see `ComposeYaml::get_ports`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ServicePort {
+    #[serde(flatten)]
+    pub port: PortMapping,
+    pub service: String,
+    /// position of the port in the `ports` attribute of the service
+    #[serde(skip)]
+    pub index: usize,
+}
+
+/// Find the ports published in a host port already published by another
+/// port, returning each port with the first port it collides with.
+///
+/// ```
+/// use docker_pose::{find_port_collisions, PortMapping, ServicePort};
+///
+/// let port = |service: &str, port: &str| ServicePort {
+///     port: PortMapping::parse(port).unwrap(),
+///     service: service.to_string(),
+///     index: 0,
+/// };
+/// let ports = vec![port("app", "8080:80"), port("web", "8080:8080"), port("db", "5432")];
+/// let collisions = find_port_collisions(&ports);
+/// assert_eq!(collisions, vec![(&ports[1], &ports[0])]);
+/// ```
+pub fn find_port_collisions(ports: &[ServicePort]) -> Vec<(&ServicePort, &ServicePort)> {
+    ports
+        .iter()
+        .enumerate()
+        .flat_map(|(i, port)| {
+            ports[..i]
+                .iter()
+                .find(|other| port.port.collides_with(&other.port))
+                .map(|other| (port, other))
+        })
+        .collect::<Vec<_>>()
+}
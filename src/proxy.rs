@@ -0,0 +1,119 @@
+/// Proxy settings from the environment: `HTTP_PROXY`, `HTTPS_PROXY`,
+/// `ALL_PROXY` and `NO_PROXY`, in lowercase or uppercase.
+use std::env;
+use url::Url;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProxyConfig {
+    /// proxy of the http URLs
+    pub http: Option<String>,
+    /// proxy of the https URLs
+    pub https: Option<String>,
+    /// hosts or domains that are accessed without proxy, `*` for all
+    pub no_proxy: Vec<String>,
+}
+
+impl ProxyConfig {
+    /// Load the settings from the environment variables, the ones in
+    /// lowercase have precedence. `ALL_PROXY` is used for the schemes
+    /// without a proxy set.
+    pub fn from_env() -> Self {
+        let all = get_env("all_proxy");
+        Self {
+            http: get_env("http_proxy").or_else(|| all.clone()),
+            https: get_env("https_proxy").or(all),
+            no_proxy: get_env("no_proxy")
+                .map(|no_proxy| parse_no_proxy(&no_proxy))
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Proxy to use to request the URL, `None` if the URL is accessed
+    /// without proxy.
+    ///
+    /// ```
+    /// use docker_pose::ProxyConfig;
+    ///
+    /// let proxy = ProxyConfig {
+    ///     http: None,
+    ///     https: Some("http://proxy.corp:3128".to_string()),
+    ///     no_proxy: vec!["internal.corp".to_string(), "localhost".to_string()],
+    /// };
+    /// assert_eq!(proxy.get_proxy("https://gitlab.com/compose.yaml"), Some("http://proxy.corp:3128"));
+    /// assert_eq!(proxy.get_proxy("https://nexus.internal.corp/compose.yaml"), None);
+    /// assert_eq!(proxy.get_proxy("http://gitlab.com/compose.yaml"), None);
+    /// ```
+    pub fn get_proxy(&self, url: &str) -> Option<&str> {
+        let url = Url::parse(url).ok()?;
+        let proxy = match url.scheme() {
+            "http" => self.http.as_deref(),
+            "https" => self.https.as_deref(),
+            _ => None,
+        }?;
+        match is_no_proxy(url.host_str()?, &self.no_proxy) {
+            true => None,
+            false => Some(proxy),
+        }
+    }
+}
+
+fn get_env(name: &str) -> Option<String> {
+    env::var(name)
+        .or_else(|_| env::var(name.to_uppercase()))
+        .ok()
+        .filter(|value| !value.is_empty())
+}
+
+/// Parse the list of hosts of `NO_PROXY`, separated by commas. The ports
+/// are removed, so the hosts are accessed without proxy in any port.
+///
+/// ```
+/// use docker_pose::parse_no_proxy;
+///
+/// assert_eq!(parse_no_proxy("localhost, .corp ,,10.0.0.1"), vec!["localhost", "corp", "10.0.0.1"]);
+/// assert_eq!(parse_no_proxy("nexus.corp:8443,[::1]:80,::1"), vec!["nexus.corp", "::1", "::1"]);
+/// ```
+pub fn parse_no_proxy(no_proxy: &str) -> Vec<String> {
+    no_proxy
+        .split(',')
+        .map(|host| {
+            let host = host.trim().trim_start_matches('.');
+            let host = match host.strip_prefix('[') {
+                // IPv6 address, with or without port
+                Some(ipv6) => ipv6.split(']').next().unwrap_or(ipv6),
+                None => match host.split_once(':') {
+                    // IPv6 addresses without brackets can't have a port
+                    Some((name, port)) if !port.contains(':') => name,
+                    _ => host,
+                },
+            };
+            host.to_lowercase()
+        })
+        .filter(|host| !host.is_empty())
+        .collect()
+}
+
+/// Whether the host is accessed without proxy: it's one of the
+/// hosts of the list, or a subdomain of one of them.
+///
+/// ```
+/// use docker_pose::is_no_proxy;
+///
+/// let no_proxy = vec!["corp".to_string(), "10.0.0.1".to_string()];
+/// assert!(is_no_proxy("nexus.corp", &no_proxy));
+/// assert!(is_no_proxy("10.0.0.1", &no_proxy));
+/// assert!(!is_no_proxy("corporate.com", &no_proxy));
+/// assert!(is_no_proxy("gitlab.com", &["*".to_string()]));
+/// ```
+pub fn is_no_proxy(host: &str, no_proxy: &[String]) -> bool {
+    let host = host.to_lowercase();
+    // IPv6 addresses are enclosed in brackets in the URLs
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    no_proxy.iter().any(|entry| {
+        entry == "*"
+            || host == entry
+            || host
+                .strip_suffix(entry.as_str())
+                .is_some_and(|sub| sub.ends_with('.'))
+    })
+}
@@ -0,0 +1,437 @@
+/// Client of the OCI Distribution API, used to check whether the images
+/// exist in the remote registries without the docker CLI:
+/// https://github.com/opencontainers/distribution-spec/blob/main/spec.md
+use crate::utils::{is_loopback_host, is_secure_url};
+use crate::{ImageRef, Verbosity};
+use clap::crate_version;
+use colored::Colorize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use std::{env, fmt, fs};
+use ureq::{Agent, AgentBuilder, Error, Response};
+use url::Url;
+
+const DOCKER_HUB_REGISTRY: &str = "registry-1.docker.io";
+
+/// Manifest formats accepted, single images and multi-platform images,
+/// otherwise some registries respond with "not found".
+const MANIFEST_TYPES: &str = "application/vnd.oci.image.index.v1+json, \
+    application/vnd.oci.image.manifest.v1+json, \
+    application/vnd.docker.distribution.manifest.list.v2+json, \
+    application/vnd.docker.distribution.manifest.v2+json, \
+    application/vnd.docker.distribution.manifest.v1+prettyjws";
+
+#[derive(Debug, PartialEq)]
+pub enum RegistryError {
+    /// the registry requires credentials, or the credentials used are not valid
+    Unauthorized(String),
+    /// the registry responded with an unexpected HTTP status
+    Status(u16, String),
+    /// the registry couldn't be reached, e.g. unknown host or connection refused
+    Transport(String),
+    /// wrong authentication challenge or token response
+    InvalidResponse(String),
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RegistryError::Unauthorized(msg) => write!(f, "unauthorized: {}", msg),
+            RegistryError::Status(code, msg) => write!(f, "HTTP {} {}", code, msg),
+            RegistryError::Transport(msg) => write!(f, "{}", msg),
+            RegistryError::InvalidResponse(msg) => write!(f, "invalid response: {}", msg),
+        }
+    }
+}
+
+pub struct RegistryClient {
+    agent: Agent,
+    /// credentials encoded in base64 ("user:password") by registry host
+    auths: HashMap<String, String>,
+    /// hosts of the registries that are accessed with plain HTTP if they
+    /// don't support HTTPS, like the `insecure-registries` of docker
+    insecure_registries: Vec<String>,
+    verbosity: Verbosity,
+    /// "Authorization" header values by registry host and repository,
+    /// so the token is requested only once for each repository
+    tokens: Mutex<HashMap<String, String>>,
+}
+
+impl RegistryClient {
+    pub fn new(
+        auths: HashMap<String, String>,
+        insecure_registries: Vec<String>,
+        verbosity: Verbosity,
+    ) -> Self {
+        let agent = AgentBuilder::new()
+            .timeout_connect(Duration::from_secs(10))
+            .timeout(Duration::from_secs(30))
+            .user_agent(format!("pose/{}", crate_version!()).as_str())
+            .build();
+        let auths = auths
+            .into_iter()
+            .map(|(registry, auth)| (normalize_registry(&registry), auth))
+            .collect();
+        Self {
+            agent,
+            auths,
+            insecure_registries,
+            verbosity,
+            tokens: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Create a client with the credentials stored by `docker login` in the
+    /// `config.json` file from the `$DOCKER_CONFIG` directory, or `~/.docker`.
+    /// Credentials stored with credential helpers are not supported. Plain HTTP
+    /// is only used with the `insecure-registries` of the docker daemon config.
+    pub fn from_docker_config(verbosity: Verbosity) -> Self {
+        let warn = |e: String| {
+            if !matches!(verbosity, Verbosity::Quiet) {
+                eprintln!("{}: {}", "WARN".yellow(), e);
+            }
+        };
+        let auths = match docker_config_dir().map(|dir| dir.join("config.json")) {
+            Some(path) if path.is_file() => read_docker_auths(&path).unwrap_or_else(|e| {
+                warn(e);
+                HashMap::new()
+            }),
+            _ => HashMap::new(),
+        };
+        let insecure_registries = daemon_config_paths()
+            .into_iter()
+            .filter(|path| path.is_file())
+            .flat_map(|path| {
+                read_docker_insecure_registries(&path).unwrap_or_else(|e| {
+                    warn(e);
+                    Vec::new()
+                })
+            })
+            .collect();
+        Self::new(auths, insecure_registries, verbosity)
+    }
+
+    /// Check whether the manifest of the image exists in the registry.
+    pub fn has_manifest(&self, image: &ImageRef) -> Result<bool, RegistryError> {
+        status_result(self.head_image(image)?)
+    }
+
+    /// Get the digest of the manifest of the image from the registry, for
+    /// multi-platform images the digest of the manifest list (or index) is
+    /// returned, so the same digest can be pulled in any platform.
+    pub fn get_digest(&self, image: &ImageRef) -> Result<Option<String>, RegistryError> {
+        let response = self.head_image(image)?;
+        let digest = response.header("docker-content-digest").map(String::from);
+        match (status_result(response)?, digest) {
+            (true, Some(digest)) => Ok(Some(digest)),
+            (true, None) => Err(RegistryError::InvalidResponse(
+                "manifest without digest".to_string(),
+            )),
+            (false, _) => Ok(None),
+        }
+    }
+
+    /// Make a HEAD request to the manifest of the image, authenticating if needed.
+    fn head_image(&self, image: &ImageRef) -> Result<Response, RegistryError> {
+        let host = registry_host(image);
+        let repository = match (&image.registry, image.repository.contains('/')) {
+            (None, false) => format!("library/{}", image.repository),
+            _ => image.repository.clone(),
+        };
+        let reference = image.digest.as_deref().unwrap_or(image.get_tag());
+        let insecure = self.insecure_registries.contains(&host);
+        let schemes: &[&str] = match (is_loopback(image), insecure) {
+            (true, _) => &["http"],
+            (false, true) => &["https", "http"],
+            (false, false) => &["https"],
+        };
+        let mut first_error = None;
+        for scheme in schemes {
+            let url = format!(
+                "{}://{}/v2/{}/manifests/{}",
+                scheme, host, repository, reference
+            );
+            match self.head_manifest(&url, &host, &repository) {
+                // the error from the first scheme tried is the most relevant
+                Err(RegistryError::Transport(e)) => {
+                    first_error.get_or_insert(RegistryError::Transport(e));
+                }
+                result => return result,
+            }
+        }
+        Err(first_error.unwrap())
+    }
+
+    fn head_manifest(
+        &self,
+        url: &str,
+        host: &str,
+        repository: &str,
+    ) -> Result<Response, RegistryError> {
+        let token_key = format!("{}/{}", host, repository);
+        let secure = is_secure_url(url);
+        let authorization = match secure {
+            true => self.tokens.lock().unwrap().get(&token_key).cloned(),
+            false => None,
+        };
+        let response = self.head(url, authorization.as_deref())?;
+        if response.status() != 401 || authorization.is_some() {
+            return Ok(response);
+        }
+        if !secure {
+            return Err(RegistryError::Unauthorized(format!(
+                "credentials are not sent to {} over plain HTTP",
+                host
+            )));
+        }
+        let challenge = response.header("www-authenticate").ok_or_else(|| {
+            RegistryError::InvalidResponse("authentication required without challenge".to_string())
+        })?;
+        let authorization = self.authenticate(challenge, host, repository)?;
+        self.tokens
+            .lock()
+            .unwrap()
+            .insert(token_key, authorization.clone());
+        self.head(url, Some(&authorization))
+    }
+
+    /// Make a HEAD request to the URL, responses with
+    /// error status codes are not considered errors.
+    fn head(&self, url: &str, authorization: Option<&str>) -> Result<Response, RegistryError> {
+        if matches!(self.verbosity, Verbosity::Verbose) {
+            eprintln!("{}: HEAD {}", "DEBUG".green(), url);
+        }
+        let mut request = self.agent.head(url).set("Accept", MANIFEST_TYPES);
+        if let Some(auth) = authorization {
+            request = request.set("Authorization", auth);
+        }
+        match request.call() {
+            Ok(response) | Err(Error::Status(_, response)) => Ok(response),
+            Err(Error::Transport(transport)) => {
+                Err(RegistryError::Transport(transport.to_string()))
+            }
+        }
+    }
+
+    /// Get the value of the "Authorization" header to use in the requests
+    /// following the challenge received from the registry: with the
+    /// "Basic" scheme the credentials are used, and with the "Bearer"
+    /// scheme a token is requested to the authorization service.
+    fn authenticate(
+        &self,
+        challenge: &str,
+        host: &str,
+        repository: &str,
+    ) -> Result<String, RegistryError> {
+        let credentials = self.get_credentials(host);
+        let (scheme, params) = parse_challenge(challenge);
+        match scheme.to_lowercase().as_str() {
+            "basic" => credentials
+                .map(|auth| format!("Basic {}", auth))
+                .ok_or_else(|| RegistryError::Unauthorized(format!("no credentials for {}", host))),
+            "bearer" => {
+                let realm = params.get("realm").ok_or_else(|| {
+                    RegistryError::InvalidResponse(format!(
+                        "challenge without realm: {}",
+                        challenge
+                    ))
+                })?;
+                let scope = match params.get("scope") {
+                    Some(scope) => scope.clone(),
+                    None => format!("repository:{}:pull", repository),
+                };
+                let mut query = vec![("scope", scope.as_str())];
+                if let Some(service) = params.get("service") {
+                    query.push(("service", service));
+                }
+                let url = Url::parse_with_params(realm, &query)
+                    .map_err(|e| RegistryError::InvalidResponse(format!("wrong realm: {}", e)))?;
+                if !is_secure_url(url.as_str()) {
+                    return Err(RegistryError::Unauthorized(format!(
+                        "token not requested to {} over plain HTTP",
+                        realm
+                    )));
+                }
+                if matches!(self.verbosity, Verbosity::Verbose) {
+                    eprintln!("{}: GET {}", "DEBUG".green(), url);
+                }
+                let mut request = self.agent.get(url.as_str());
+                if let Some(auth) = credentials {
+                    request = request.set("Authorization", &format!("Basic {}", auth));
+                }
+                let response = match request.call() {
+                    Ok(response) => response,
+                    Err(Error::Status(401 | 403, _)) => {
+                        return Err(RegistryError::Unauthorized(format!(
+                            "token for {} denied by {}",
+                            repository, realm
+                        )))
+                    }
+                    Err(Error::Status(_, response)) => return Err(registry_error(response)),
+                    Err(Error::Transport(transport)) => {
+                        return Err(RegistryError::Transport(transport.to_string()))
+                    }
+                };
+                let body: Value = response
+                    .into_string()
+                    .map_err(|e| e.to_string())
+                    .and_then(|body| serde_json::from_str(&body).map_err(|e| e.to_string()))
+                    .map_err(|e| RegistryError::InvalidResponse(format!("token: {}", e)))?;
+                body.get("token")
+                    .or_else(|| body.get("access_token"))
+                    .and_then(|token| token.as_str())
+                    .map(|token| format!("Bearer {}", token))
+                    .ok_or_else(|| {
+                        RegistryError::InvalidResponse("token not found in response".to_string())
+                    })
+            }
+            _ => Err(RegistryError::InvalidResponse(format!(
+                "authentication scheme not supported: {}",
+                challenge
+            ))),
+        }
+    }
+
+    fn get_credentials(&self, host: &str) -> Option<&String> {
+        match host {
+            DOCKER_HUB_REGISTRY => ["index.docker.io", "docker.io", DOCKER_HUB_REGISTRY]
+                .iter()
+                .find_map(|h| self.auths.get(*h)),
+            _ => self.auths.get(host),
+        }
+    }
+}
+
+/// Map the responses that are not successful into the result of the check.
+fn status_result(response: Response) -> Result<bool, RegistryError> {
+    match response.status() {
+        200..=299 => Ok(true),
+        404 => Ok(false),
+        _ => Err(registry_error(response)),
+    }
+}
+
+fn registry_error(response: Response) -> RegistryError {
+    match response.status() {
+        401 | 403 => RegistryError::Unauthorized(
+            response
+                .into_string()
+                .unwrap_or_default()
+                .trim()
+                .to_string(),
+        ),
+        code => RegistryError::Status(code, response.status_text().to_string()),
+    }
+}
+
+/// Parse an authentication challenge like
+/// `Bearer realm="https://auth.docker.io/token",service="registry.docker.io"`.
+fn parse_challenge(challenge: &str) -> (String, HashMap<String, String>) {
+    let (scheme, rest) = challenge.trim().split_once(' ').unwrap_or((challenge, ""));
+    let mut params = HashMap::new();
+    let mut rest = rest.trim();
+    while let Some((key, value)) = rest.split_once('=') {
+        let key = key.trim().trim_start_matches(',').trim().to_lowercase();
+        let (value, remainder) = match value.strip_prefix('"') {
+            Some(quoted) => match quoted.find('"') {
+                Some(end) => (&quoted[..end], &quoted[end + 1..]),
+                None => (quoted, ""),
+            },
+            None => value.split_once(',').unwrap_or((value, "")),
+        };
+        params.insert(key, value.to_string());
+        rest = remainder.trim_start_matches(',').trim();
+    }
+    (scheme.to_string(), params)
+}
+
+/// Host of the registry of the image, including the port.
+fn registry_host(image: &ImageRef) -> String {
+    match (&image.registry, image.port) {
+        (Some(registry), Some(port)) => format!("{}:{}", registry, port),
+        (Some(registry), None) if registry == "docker.io" => DOCKER_HUB_REGISTRY.to_string(),
+        (Some(registry), None) => registry.clone(),
+        (None, _) => DOCKER_HUB_REGISTRY.to_string(),
+    }
+}
+
+/// Like docker, registries in the loopback interface are accessed with plain HTTP.
+fn is_loopback(image: &ImageRef) -> bool {
+    image.registry.as_deref().is_some_and(is_loopback_host)
+}
+
+/// The keys of the credentials stored by docker can be URLs,
+/// e.g. `https://index.docker.io/v1/`, only the host is used.
+fn normalize_registry(registry: &str) -> String {
+    let registry = registry
+        .strip_prefix("https://")
+        .or_else(|| registry.strip_prefix("http://"))
+        .unwrap_or(registry);
+    registry.split('/').next().unwrap_or(registry).to_string()
+}
+
+fn docker_config_dir() -> Option<PathBuf> {
+    match env::var("DOCKER_CONFIG") {
+        Ok(dir) => Some(PathBuf::from(dir)),
+        Err(_) => env::var("HOME")
+            .ok()
+            .map(|home| Path::new(&home).join(".docker")),
+    }
+}
+
+/// Paths of the docker daemon config: the one of Docker Desktop
+/// in the docker config directory, and the one of Docker Engine.
+fn daemon_config_paths() -> Vec<PathBuf> {
+    let mut paths = docker_config_dir()
+        .map(|dir| vec![dir.join("daemon.json")])
+        .unwrap_or_default();
+    paths.push(PathBuf::from("/etc/docker/daemon.json"));
+    paths
+}
+
+/// Read the credentials from the `auths` section of a docker `config.json` file.
+pub fn read_docker_auths(path: &Path) -> Result<HashMap<String, String>, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("reading docker config '{}': {}", path.display(), e))?;
+    let config: Value = serde_json::from_str(&content)
+        .map_err(|e| format!("parsing docker config '{}': {}", path.display(), e))?;
+    Ok(config
+        .get("auths")
+        .and_then(|auths| auths.as_object())
+        .map(|auths| {
+            auths
+                .iter()
+                .flat_map(|(registry, v)| {
+                    let auth = v.get("auth")?.as_str().filter(|a| !a.is_empty())?;
+                    Some((registry.clone(), auth.to_string()))
+                })
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+/// Read the hosts from the `insecure-registries` section of a docker `daemon.json`
+/// file, the registries in CIDR notation (e.g. `10.0.0.0/8`) are not supported.
+pub fn read_docker_insecure_registries(path: &Path) -> Result<Vec<String>, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("reading docker daemon config '{}': {}", path.display(), e))?;
+    let config: Value = serde_json::from_str(&content)
+        .map_err(|e| format!("parsing docker daemon config '{}': {}", path.display(), e))?;
+    Ok(config
+        .get("insecure-registries")
+        .and_then(|registries| registries.as_array())
+        .map(|registries| {
+            registries
+                .iter()
+                .flat_map(|registry| registry.as_str())
+                .filter(|registry| {
+                    !matches!(registry.rsplit_once('/'), Some((_, mask)) if mask.parse::<u8>().is_ok())
+                })
+                .map(normalize_registry)
+                .collect()
+        })
+        .unwrap_or_default())
+}
@@ -0,0 +1,618 @@
+/// Validation of compose files against the JSON schema of the Compose
+/// specification, bundled in the binary so no network access is needed.
+/// Only the keywords used by the Compose schema are supported.
+use crate::{Interpolator, PoseError};
+use regex::Regex;
+use serde_json::Value as Json;
+use serde_yaml::Value;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+
+/// https://github.com/compose-spec/compose-spec/blob/master/schema/compose-spec.json
+const COMPOSE_SPEC: &str = include_str!("compose-spec.json");
+
+/// An element of the path of a value: a key of a mapping or an index of a list.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+/// A value that doesn't follow the schema.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SchemaError {
+    pub path: Vec<PathSegment>,
+    /// line number of the value in the file, if it was found
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl SchemaError {
+    /// Path of the value in the same notation used by the interpolation
+    /// errors, e.g. `services.app.ports[0]`.
+    ///
+    /// ```
+    /// use docker_pose::{PathSegment, SchemaError};
+    ///
+    /// let error = SchemaError {
+    ///     path: vec![
+    ///         PathSegment::Key("services".to_string()),
+    ///         PathSegment::Key("app".to_string()),
+    ///         PathSegment::Key("ports".to_string()),
+    ///         PathSegment::Index(0),
+    ///     ],
+    ///     line: None,
+    ///     message: "must be a string".to_string(),
+    /// };
+    /// assert_eq!(error.path_str(), "services.app.ports[0]");
+    /// ```
+    pub fn path_str(&self) -> String {
+        let mut path = String::new();
+        for segment in &self.path {
+            match segment {
+                PathSegment::Key(key) if path.is_empty() => path.push_str(key),
+                PathSegment::Key(key) => {
+                    path.push('.');
+                    path.push_str(key);
+                }
+                PathSegment::Index(i) => path.push_str(&format!("[{}]", i)),
+            }
+        }
+        path
+    }
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.path.is_empty() {
+            true => write!(f, "{}", self.message),
+            false => write!(f, "{}: {}", self.path_str(), self.message),
+        }
+    }
+}
+
+pub struct SchemaValidator {
+    schema: Json,
+    regexes: RefCell<HashMap<String, Regex>>,
+}
+
+impl SchemaValidator {
+    pub fn new(schema: Json) -> Self {
+        Self {
+            schema,
+            regexes: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Validator with the Compose specification schema.
+    pub fn compose_spec() -> Self {
+        Self::new(serde_json::from_str(COMPOSE_SPEC).expect("invalid compose spec schema"))
+    }
+
+    /// Validate the value against the schema, returning all the violations found.
+    pub fn validate(&self, value: &Value) -> Vec<SchemaError> {
+        let mut errors = Vec::new();
+        self.validate_node(value, &self.schema, &mut Vec::new(), &mut errors);
+        errors
+    }
+
+    fn validate_node(
+        &self,
+        value: &Value,
+        schema: &Json,
+        path: &mut Vec<PathSegment>,
+        errors: &mut Vec<SchemaError>,
+    ) {
+        let value = untag(value);
+        let schema = self.resolve(schema);
+        let mut add_error = |path: &[PathSegment], message: String| {
+            errors.push(SchemaError {
+                path: path.to_vec(),
+                line: None,
+                message,
+            })
+        };
+        if let Some(types) = schema_types(schema) {
+            if !types.iter().any(|t| is_type(value, t)) {
+                add_error(
+                    path,
+                    format!(
+                        "must be {}, but got {}",
+                        types
+                            .iter()
+                            .map(|t| type_name(t))
+                            .collect::<Vec<_>>()
+                            .join(" or "),
+                        value_type_name(value)
+                    ),
+                );
+                // the other keywords would report errors of the same value
+                return;
+            }
+        }
+        if let Some(allowed) = schema.get("enum").and_then(|e| e.as_array()) {
+            if !allowed.iter().any(|a| json_eq(value, a)) {
+                add_error(
+                    path,
+                    format!(
+                        "must be one of: {}",
+                        allowed
+                            .iter()
+                            .map(|a| a.to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                );
+            }
+        }
+        if let (Some(pattern), Value::String(s)) =
+            (schema.get("pattern").and_then(|p| p.as_str()), value)
+        {
+            if !self.is_match(pattern, s) {
+                add_error(
+                    path,
+                    format!("'{}' doesn't match the pattern '{}'", s, pattern),
+                );
+            }
+        }
+        if let Some(n) = value.as_f64() {
+            if let Some(min) = schema.get("minimum").and_then(|m| m.as_f64()) {
+                if n < min {
+                    add_error(path, format!("must be greater than or equal to {}", min));
+                }
+            }
+            if let Some(max) = schema.get("maximum").and_then(|m| m.as_f64()) {
+                if n > max {
+                    add_error(path, format!("must be less than or equal to {}", max));
+                }
+            }
+        }
+        match value {
+            Value::Mapping(map) => self.validate_mapping(map, schema, path, errors),
+            Value::Sequence(seq) => self.validate_sequence(seq, schema, path, errors),
+            _ => {}
+        }
+        for keyword in ["oneOf", "anyOf"] {
+            if let Some(branches) = schema.get(keyword).and_then(|b| b.as_array()) {
+                self.validate_branches(value, branches, keyword == "oneOf", path, errors);
+            }
+        }
+    }
+
+    fn validate_mapping(
+        &self,
+        map: &serde_yaml::Mapping,
+        schema: &Json,
+        path: &mut Vec<PathSegment>,
+        errors: &mut Vec<SchemaError>,
+    ) {
+        if let Some(required) = schema.get("required").and_then(|r| r.as_array()) {
+            for key in required.iter().flat_map(|k| k.as_str()) {
+                if !map.contains_key(key) {
+                    errors.push(SchemaError {
+                        path: path.clone(),
+                        line: None,
+                        message: format!("missing required property '{}'", key),
+                    });
+                }
+            }
+        }
+        let properties = schema.get("properties").and_then(|p| p.as_object());
+        let pattern_properties = schema.get("patternProperties").and_then(|p| p.as_object());
+        for (key, val) in map {
+            let key = match key {
+                Value::String(key) => key.clone(),
+                Value::Number(n) => n.to_string(),
+                Value::Bool(b) => b.to_string(),
+                _ => to_string(key),
+            };
+            path.push(PathSegment::Key(key.clone()));
+            let mut matched = false;
+            if let Some(prop_schema) = properties.and_then(|p| p.get(&key)) {
+                matched = true;
+                self.validate_node(val, prop_schema, path, errors);
+            }
+            for (pattern, prop_schema) in pattern_properties.into_iter().flatten() {
+                if self.is_match(pattern, &key) {
+                    matched = true;
+                    self.validate_node(val, prop_schema, path, errors);
+                }
+            }
+            if !matched {
+                match schema.get("additionalProperties") {
+                    Some(Json::Bool(false)) => errors.push(SchemaError {
+                        path: path.clone(),
+                        line: None,
+                        message: "additional property not allowed".to_string(),
+                    }),
+                    Some(additional @ Json::Object(_)) => {
+                        self.validate_node(val, additional, path, errors)
+                    }
+                    _ => {}
+                }
+            }
+            path.pop();
+        }
+    }
+
+    fn validate_sequence(
+        &self,
+        seq: &[Value],
+        schema: &Json,
+        path: &mut Vec<PathSegment>,
+        errors: &mut Vec<SchemaError>,
+    ) {
+        let unique = schema.get("uniqueItems").and_then(|u| u.as_bool()) == Some(true);
+        for (i, item) in seq.iter().enumerate() {
+            path.push(PathSegment::Index(i));
+            if let Some(items) = schema.get("items") {
+                self.validate_node(item, items, path, errors);
+            }
+            if unique && seq[..i].contains(item) {
+                errors.push(SchemaError {
+                    path: path.clone(),
+                    line: None,
+                    message: "duplicated item".to_string(),
+                });
+            }
+            path.pop();
+        }
+    }
+
+    /// Validate the `oneOf` or `anyOf` alternatives. When no alternative matches,
+    /// the errors reported are the ones from the alternative that accepts the type
+    /// of the value, e.g. the long syntax of a port when the value is a mapping.
+    fn validate_branches(
+        &self,
+        value: &Value,
+        branches: &[Json],
+        only_one: bool,
+        path: &mut Vec<PathSegment>,
+        errors: &mut Vec<SchemaError>,
+    ) {
+        let results = branches
+            .iter()
+            .map(|branch| {
+                let mut branch_errors = Vec::new();
+                self.validate_node(value, branch, path, &mut branch_errors);
+                (branch, branch_errors)
+            })
+            .collect::<Vec<_>>();
+        let valid = results.iter().filter(|(_, e)| e.is_empty()).count();
+        if valid == 1 || (valid > 1 && !only_one) {
+            return;
+        }
+        if valid > 1 {
+            errors.push(SchemaError {
+                path: path.clone(),
+                line: None,
+                message: "matches more than one of the allowed definitions".to_string(),
+            });
+            return;
+        }
+        let type_matches = results
+            .iter()
+            .filter(|(branch, _)| {
+                self.branch_types(branch)
+                    .map(|types| types.iter().any(|t| is_type(value, t)))
+                    .unwrap_or(true)
+            })
+            .collect::<Vec<_>>();
+        match type_matches.iter().min_by_key(|(_, e)| e.len()) {
+            Some((_, branch_errors)) => errors.extend(branch_errors.iter().cloned()),
+            None => {
+                let mut types = results
+                    .iter()
+                    .flat_map(|(branch, _)| self.branch_types(branch).unwrap_or_default())
+                    .map(type_name)
+                    .collect::<Vec<_>>();
+                types.dedup();
+                errors.push(SchemaError {
+                    path: path.clone(),
+                    line: None,
+                    message: format!(
+                        "must be {}, but got {}",
+                        types.join(" or "),
+                        value_type_name(value)
+                    ),
+                });
+            }
+        }
+    }
+
+    /// Types accepted by the alternative, following the nested alternatives.
+    fn branch_types<'a>(&'a self, branch: &'a Json) -> Option<Vec<&'a str>> {
+        let branch = self.resolve(branch);
+        if let Some(types) = schema_types(branch) {
+            return Some(types);
+        }
+        let alternatives = branch
+            .get("oneOf")
+            .or_else(|| branch.get("anyOf"))?
+            .as_array()?;
+        let mut types = Vec::new();
+        for alternative in alternatives {
+            types.extend(self.branch_types(alternative)?);
+        }
+        Some(types)
+    }
+
+    /// Follow the `$ref` of the schema, only local references to
+    /// the `definitions` are supported.
+    fn resolve<'a>(&'a self, schema: &'a Json) -> &'a Json {
+        match schema.get("$ref").and_then(|r| r.as_str()) {
+            Some(reference) => {
+                let pointer = reference.trim_start_matches('#');
+                match self.schema.pointer(pointer) {
+                    Some(resolved) => self.resolve(resolved),
+                    None => schema,
+                }
+            }
+            None => schema,
+        }
+    }
+
+    fn is_match(&self, pattern: &str, text: &str) -> bool {
+        let mut regexes = self.regexes.borrow_mut();
+        if !regexes.contains_key(pattern) {
+            match Regex::new(pattern) {
+                Ok(regex) => regexes.insert(pattern.to_string(), regex),
+                // patterns not supported are ignored
+                Err(_) => return true,
+            };
+        }
+        regexes[pattern].is_match(text)
+    }
+}
+
+/// Validate the content of a compose file against the Compose specification,
+/// returning all the violations found with the line where each value is defined.
+/// If an interpolator is passed, the values are interpolated before the validation.
+///
+/// ```
+/// use docker_pose::validate_compose_file;
+///
+/// let yaml = "
+/// services:
+///   app:
+///     image: app
+///     ports:
+///       - 8080:80
+///     restart: [always]
+/// ";
+/// let errors = validate_compose_file(yaml, None).unwrap();
+/// assert_eq!(errors.len(), 1);
+/// assert_eq!(errors[0].line, Some(7));
+/// assert_eq!(errors[0].to_string(), "services.app.restart: must be a string, but got a list");
+/// ```
+pub fn validate_compose_file(
+    content: &str,
+    interpolator: Option<&Interpolator>,
+) -> Result<Vec<SchemaError>, PoseError> {
+    let mut value: Value =
+        serde_yaml::from_str(content).map_err(|e| PoseError::ParseYaml(e.to_string()))?;
+    value
+        .apply_merge()
+        .map_err(|e| PoseError::ParseYaml(e.to_string()))?;
+    if let (Some(interpolator), Value::Mapping(map)) = (interpolator, &mut value) {
+        for (key, val) in map.iter_mut() {
+            interpolator
+                .interpolate_value(val, key.as_str().unwrap_or_default())
+                .map_err(PoseError::Interpolation)?;
+        }
+    }
+    let mut errors = SchemaValidator::compose_spec().validate(&value);
+    for error in errors.iter_mut() {
+        error.line = find_line(content, &error.path);
+    }
+    errors.sort_by_key(|e| e.line.unwrap_or(usize::MAX));
+    Ok(errors)
+}
+
+/// Find the line number where the value of the path is defined in the YAML
+/// content. Only collections in block style are followed, for the values
+/// inside a collection in flow style, e.g. `[a, b]`, the line of the
+/// collection is returned.
+///
+/// ```
+/// use docker_pose::{find_line, PathSegment};
+///
+/// let yaml = "services:\n  app:\n    ports:\n      - 80:80\n      - 443:443\n";
+/// let path = vec![
+///     PathSegment::Key("services".to_string()),
+///     PathSegment::Key("app".to_string()),
+///     PathSegment::Key("ports".to_string()),
+///     PathSegment::Index(1),
+/// ];
+/// assert_eq!(find_line(yaml, &path), Some(5));
+/// ```
+pub fn find_line(content: &str, path: &[PathSegment]) -> Option<usize> {
+    find_path(content, path).map(|(line, _)| line)
+}
+
+/// Same as `find_line`, but returning as well the number
+/// of segments of the path that were found.
+pub(crate) fn find_path(content: &str, path: &[PathSegment]) -> Option<(usize, usize)> {
+    let lines = content
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            let text = line.trim_start();
+            (i, line.len() - text.len(), text)
+        })
+        .filter(|(_, _, text)| !text.is_empty() && !text.starts_with('#'))
+        .filter(|(_, _, text)| *text != "---" && *text != "...")
+        .collect::<Vec<_>>();
+    let mut node = Node {
+        pos: None,
+        col: None,
+        inline: None,
+        is_item: false,
+    };
+    let mut found = None;
+    for (depth, segment) in path.iter().enumerate() {
+        let children = node.children(&lines);
+        let child = match segment {
+            PathSegment::Key(key) => children
+                .into_iter()
+                .find(|(_, _, text)| is_key_line(text, key))
+                .map(|(pos, col, _)| Node {
+                    pos: Some(pos),
+                    col: Some(col),
+                    inline: None,
+                    is_item: false,
+                }),
+            PathSegment::Index(index) => children
+                .into_iter()
+                .filter(|(_, _, text)| *text == "-" || text.starts_with("- "))
+                .nth(*index)
+                .map(|(pos, col, text)| {
+                    let item = text[1..].trim_start();
+                    Node {
+                        pos: Some(pos),
+                        col: Some(col),
+                        inline: (!item.is_empty()).then(|| col + text.len() - item.len()),
+                        is_item: true,
+                    }
+                }),
+        };
+        match child {
+            Some(child) => {
+                found = child.pos.map(|pos| (lines[pos].0 + 1, depth + 1));
+                node = child;
+            }
+            None => break,
+        }
+    }
+    found
+}
+
+/// A node of the YAML content being followed by `find_line`.
+struct Node {
+    /// position in the lines of the line where the node is, `None` for the root
+    pos: Option<usize>,
+    /// column where the key or the "-" of the node is
+    col: Option<usize>,
+    /// column where the content of a list item starts, if it's
+    /// in the same line of the "-", e.g. `- key: value`
+    inline: Option<usize>,
+    is_item: bool,
+}
+
+impl Node {
+    /// Lines of the direct children of the node, with the position
+    /// in the lines, the column and the text of each one.
+    fn children<'a>(&self, lines: &[(usize, usize, &'a str)]) -> Vec<(usize, usize, &'a str)> {
+        let mut children = Vec::new();
+        let mut level = None;
+        if let (Some(pos), Some(inline)) = (self.pos, self.inline) {
+            let (_, indent, text) = lines[pos];
+            children.push((pos, inline, &text[inline - indent..]));
+            level = Some(inline);
+        }
+        let start = self.pos.map(|p| p + 1).unwrap_or(0);
+        for (pos, (_, indent, text)) in lines.iter().enumerate().skip(start) {
+            if let Some(col) = self.col {
+                // a list can be at the same level of its key
+                let is_list = !self.is_item && (*text == "-" || text.starts_with("- "));
+                if *indent < col || (*indent == col && !is_list) {
+                    break;
+                }
+            }
+            match level {
+                Some(level) if *indent != level => continue,
+                Some(_) => {}
+                None => level = Some(*indent),
+            }
+            children.push((pos, *indent, *text));
+        }
+        children
+    }
+}
+
+/// Whether the line starts with the key passed, quoted or not.
+fn is_key_line(text: &str, key: &str) -> bool {
+    [
+        key.to_string(),
+        format!("\"{}\"", key),
+        format!("'{}'", key),
+    ]
+    .iter()
+    .any(|k| {
+        text.strip_prefix(k.as_str())
+            .map(|rest| rest.trim_start().starts_with(':'))
+            .unwrap_or(false)
+    })
+}
+
+fn untag(value: &Value) -> &Value {
+    match value {
+        Value::Tagged(tagged) => untag(&tagged.value),
+        _ => value,
+    }
+}
+
+fn schema_types(schema: &Json) -> Option<Vec<&str>> {
+    match schema.get("type")? {
+        Json::String(t) => Some(vec![t.as_str()]),
+        Json::Array(types) => Some(types.iter().flat_map(|t| t.as_str()).collect()),
+        _ => None,
+    }
+}
+
+fn is_type(value: &Value, schema_type: &str) -> bool {
+    match schema_type {
+        "object" => value.is_mapping(),
+        "array" => value.is_sequence(),
+        "string" => value.is_string(),
+        "boolean" => value.is_bool(),
+        "null" => value.is_null(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        _ => true,
+    }
+}
+
+/// Name of the schema type in the YAML jargon.
+fn type_name(schema_type: &str) -> &str {
+    match schema_type {
+        "object" => "a mapping",
+        "array" => "a list",
+        "string" => "a string",
+        "boolean" => "a boolean",
+        "number" => "a number",
+        "integer" => "an integer",
+        t => t,
+    }
+}
+
+fn value_type_name(value: &Value) -> &str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(n) if n.is_f64() => "a number",
+        Value::Number(_) => "an integer",
+        Value::String(_) => "a string",
+        Value::Sequence(_) => "a list",
+        Value::Mapping(_) => "a mapping",
+        Value::Tagged(_) => "a tagged value",
+    }
+}
+
+fn json_eq(value: &Value, json: &Json) -> bool {
+    match (value, json) {
+        (Value::String(s), Json::String(j)) => s == j,
+        (Value::Bool(b), Json::Bool(j)) => b == j,
+        (Value::Number(n), Json::Number(j)) => n.as_f64() == j.as_f64(),
+        (Value::Null, Json::Null) => true,
+        _ => false,
+    }
+}
+
+fn to_string(value: &Value) -> String {
+    serde_yaml::to_string(value)
+        .unwrap_or_default()
+        .trim_end()
+        .to_string()
+}
@@ -1,30 +1,29 @@
-use crate::{get_compose_filename, ComposeYaml, Formats, Verbosity};
-use colored::Colorize;
+use crate::{get_compose_filename, ComposeYaml, Formats, PoseError, TagCache, Verbosity};
 use regex::Regex;
+use serde::Serialize;
 use serde_yaml::Mapping;
 use std::cmp::min;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::vec::IntoIter;
-use std::{fs, process};
+use url::Url;
 
-/// Get the tag value (or None), or exit if the filter
+/// Get the tag value (or None), or an error if the filter
 /// passed doesn't star with "tag=" prefix.
-pub fn unwrap_filter_tag(filter: Option<&str>) -> Option<&str> {
-    filter.as_ref().map(|f| {
-        if let Some(val) = f.strip_prefix("tag=") {
-            val
-        } else {
-            eprintln!(
-                "{}: wrong filter '{}', only '{}' filter supported",
-                "ERROR".red(),
-                f.yellow(),
-                "tag=".yellow()
-            );
-            process::exit(2);
-        }
-    })
+pub fn unwrap_filter_tag(filter: Option<&str>) -> Result<Option<&str>, PoseError> {
+    filter
+        .map(|f| {
+            f.strip_prefix("tag=").ok_or_else(|| {
+                PoseError::InvalidArgument(format!(
+                    "wrong filter '{}', only 'tag=' filter supported",
+                    f
+                ))
+            })
+        })
+        .transpose()
 }
 
-/// Get the regex value expressed in &str, or exit if the filter
+/// Get the regex value expressed in &str, or an error if the filter
 /// passed doesn't star with "regex=" or "regex!=" prefixes.
 /// When expression has "=" the bool is true, when is "!="
 /// the bool is false.
@@ -32,90 +31,167 @@ pub fn unwrap_filter_tag(filter: Option<&str>) -> Option<&str> {
 ///
 /// ```
 /// use regex::Regex;
-/// use docker_pose::unwrap_filter_regex;
+/// use docker_pose::{unwrap_filter_regex, PoseError};
 ///
-/// assert!(unwrap_filter_regex(None).is_none());
+/// assert!(unwrap_filter_regex(None).unwrap().is_none());
 ///
 /// let expected_regex = Regex::new("mrsarm/").unwrap();
-/// let filter = unwrap_filter_regex(Some("regex=mrsarm/"));
+/// let filter = unwrap_filter_regex(Some("regex=mrsarm/")).unwrap();
 ///
 /// assert!(filter.is_some());
 /// let filter = filter.unwrap();
 /// assert_eq!(filter.0.as_str(), expected_regex.as_str());
 /// assert_eq!(filter.1, true);
 ///
-/// let filter = unwrap_filter_regex(Some("regex!=mrsarm/"));
+/// let filter = unwrap_filter_regex(Some("regex!=mrsarm/")).unwrap();
 ///
 /// assert!(filter.is_some());
 /// let filter = filter.unwrap();
 /// assert_eq!(filter.0.as_str(), expected_regex.as_str());
 /// assert_eq!(filter.1, false);
+///
+/// let filter = unwrap_filter_regex(Some("tag=mrsarm/"));
+/// assert!(matches!(filter, Err(PoseError::InvalidArgument(_))));
 /// ```
-pub fn unwrap_filter_regex(filter: Option<&str>) -> Option<(Regex, bool)> {
-    filter.as_ref().map(|f| {
-        if let Some(val) = f.strip_prefix("regex=") {
-            let regex = Regex::new(val).unwrap_or_else(|e| {
-                invalid_regex_exit(e, val);
-            });
-            return (regex, true);
-        }
-        if let Some(val) = f.strip_prefix("regex!=") {
-            let regex = Regex::new(val).unwrap_or_else(|e| {
-                invalid_regex_exit(e, val);
-            });
-            return (regex, false);
-        }
-        eprintln!(
-            "{}: wrong filter '{}', only '{}' or '{}' filters are supported",
-            "ERROR".red(),
-            f.yellow(),
-            "regex=".yellow(),
-            "regex!=".yellow()
-        );
-        process::exit(2);
-    })
+pub fn unwrap_filter_regex(filter: Option<&str>) -> Result<Option<(Regex, bool)>, PoseError> {
+    filter
+        .map(|f| {
+            if let Some(val) = f.strip_prefix("regex=") {
+                return Ok((get_filter_regex(val)?, true));
+            }
+            if let Some(val) = f.strip_prefix("regex!=") {
+                return Ok((get_filter_regex(val)?, false));
+            }
+            Err(PoseError::InvalidArgument(format!(
+                "wrong filter '{}', only 'regex=' or 'regex!=' filters are supported",
+                f
+            )))
+        })
+        .transpose()
 }
 
-fn invalid_regex_exit(e: regex::Error, val: &str) -> ! {
-    eprintln!(
-        "{}: invalid regex expression '{}' in filter - {}",
-        "ERROR".red(),
-        val.yellow(),
-        e
-    );
-    process::exit(2);
+fn get_filter_regex(val: &str) -> Result<Regex, PoseError> {
+    Regex::new(val).map_err(|e| {
+        PoseError::InvalidArgument(format!(
+            "invalid regex expression '{}' in filter - {}",
+            val, e
+        ))
+    })
 }
 
-pub fn print_names(iter: IntoIter<&str>, pretty: Formats) {
+pub fn print_names(iter: IntoIter<&str>, pretty: Formats) -> Result<(), PoseError> {
     match pretty {
         Formats::Full => iter.for_each(|service| println!("{}", service)),
         Formats::Oneline => println!("{}", iter.collect::<Vec<&str>>().join(" ")),
+        Formats::Json | Formats::Yaml | Formats::Tsv => {
+            let names = iter.collect::<Vec<&str>>();
+            print_records(&names, pretty, |name| vec![name.to_string()])?;
+        }
     }
+    Ok(())
 }
 
-pub fn get_service<'a>(compose: &'a ComposeYaml, service_name: &str) -> &'a Mapping {
-    let service = compose.get_service(service_name);
-    match service {
-        None => {
-            eprintln!("{}: No such service found: {}", "ERROR".red(), service_name);
-            process::exit(16);
-        }
-        Some(serv) => serv,
+/// Print the records in one of the structured formats: JSON, YAML,
+/// or TSV, where `to_row` is used to get the columns of each record.
+/// With other formats the records are printed as TSV as well.
+pub fn print_records<T, F>(records: &[T], pretty: Formats, to_row: F) -> Result<(), PoseError>
+where
+    T: Serialize,
+    F: Fn(&T) -> Vec<String>,
+{
+    let output = match pretty {
+        Formats::Json => serde_json::to_string_pretty(records).map_err(|e| e.to_string()),
+        Formats::Yaml => serde_yaml::to_string(records).map_err(|e| e.to_string()),
+        Formats::Full | Formats::Oneline | Formats::Tsv => Ok(records
+            .iter()
+            .map(|r| {
+                to_row(r)
+                    .iter()
+                    .map(|col| col.replace('\t', "\\t").replace('\n', "\\n"))
+                    .collect::<Vec<_>>()
+                    .join("\t")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")),
+    };
+    let out = output.map_err(|e| PoseError::Serialize(format!("serializing output: {}", e)))?;
+    println!("{}", out.trim_end());
+    Ok(())
+}
+
+/// Print the rows in a table with the columns aligned, and the header
+/// passed in the first line.
+///
+/// ```
+/// use docker_pose::print_table;
+///
+/// print_table(&["NAME", "IMAGE"], vec![vec!["app".to_string(), "app:1.0".to_string()]]);
+/// ```
+pub fn print_table(header: &[&str], mut rows: Vec<Vec<String>>) {
+    rows.insert(0, header.iter().map(|h| h.to_string()).collect());
+    let widths = (0..header.len())
+        .map(|i| {
+            rows.iter()
+                .flat_map(|row| row.get(i))
+                .map(|c| c.len())
+                .max()
+        })
+        .map(Option::unwrap_or_default)
+        .collect::<Vec<_>>();
+    for row in rows {
+        let line = row
+            .iter()
+            .zip(&widths)
+            .map(|(col, width)| format!("{:width$}", col, width = width))
+            .collect::<Vec<_>>();
+        println!("{}", line.join("  ").trim_end());
     }
 }
 
-pub fn get_yml_content(filename: Option<&str>, verbosity: Verbosity) -> String {
-    let filename = get_compose_filename(filename, verbosity).unwrap_or_else(|err| {
-        eprintln!("{}: {}", "ERROR".red(), err);
-        if err.contains("no such file or directory") {
-            process::exit(1);
-        }
-        process::exit(10);
-    });
-    fs::read_to_string(filename).unwrap_or_else(|err| {
-        eprintln!("{}: reading compose file: {}", "ERROR".red(), err);
-        process::exit(11);
-    })
+pub fn get_service<'a>(
+    compose: &'a ComposeYaml,
+    service_name: &str,
+) -> Result<&'a Mapping, PoseError> {
+    compose
+        .get_service(service_name)
+        .ok_or_else(|| PoseError::ServiceNotFound(service_name.to_string()))
+}
+
+pub fn get_yml_content(filename: Option<&str>, verbosity: Verbosity) -> Result<String, PoseError> {
+    let filename = get_compose_filename(filename, verbosity)?;
+    fs::read_to_string(filename)
+        .map_err(|err| PoseError::ReadFile(format!("reading compose file: {}", err)))
+}
+
+/// Get the project directory, where the first compose file is located,
+/// or the current directory if no file is passed.
+///
+/// ```
+/// use std::path::PathBuf;
+/// use docker_pose::get_project_dir;
+///
+/// assert_eq!(get_project_dir(&[]), PathBuf::from("."));
+/// assert_eq!(get_project_dir(&["compose.yaml".to_string()]), PathBuf::from("."));
+/// assert_eq!(
+///     get_project_dir(&["tests/compose.yaml".to_string(), "ci.yaml".to_string()]),
+///     PathBuf::from("tests")
+/// );
+/// ```
+pub fn get_project_dir(filenames: &[String]) -> PathBuf {
+    filenames
+        .first()
+        .and_then(|f| Path::new(f).parent())
+        .filter(|p| !p.as_os_str().is_empty())
+        .map(Path::to_path_buf)
+        .unwrap_or(PathBuf::from("."))
+}
+
+/// Directory of the remote tags cache, `None` if the cache is disabled.
+pub fn get_cache_dir(no_cache: bool, cache_dir: Option<String>) -> Option<PathBuf> {
+    match no_cache {
+        true => None,
+        false => cache_dir.map(PathBuf::from).or_else(TagCache::default_dir),
+    }
 }
 
 /// Get a slug version of the text compatible with
@@ -147,3 +223,15 @@ pub fn get_slug(input: &str) -> String {
     }
     result
 }
+
+pub(crate) fn is_loopback_host(host: &str) -> bool {
+    matches!(host, "localhost" | "127.0.0.1" | "[::1]")
+}
+
+/// Whether credentials can be sent to the URL: only with HTTPS, or to
+/// the loopback interface, so they are not leaked in plain HTTP, e.g. if
+/// an attacker forces the fallback to plain HTTP of a registry.
+pub(crate) fn is_secure_url(url: &str) -> bool {
+    Url::parse(url)
+        .is_ok_and(|url| url.scheme() == "https" || url.host_str().is_some_and(is_loopback_host))
+}
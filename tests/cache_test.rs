@@ -0,0 +1,61 @@
+use docker_pose::{TagCache, Verbosity};
+use pretty_assertions::assert_eq;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+fn cache_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("pose-cache-test-{}", name));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+#[test]
+fn save_and_load_cache() {
+    let dir = cache_dir("save");
+    let cache = TagCache::load(&dir, Verbosity::Quiet);
+    assert_eq!(cache.get("docker.io/library/postgres:16.2"), None);
+    cache.insert("docker.io/library/postgres:16.2", true);
+    cache.insert("docker.io/mrsarm/app:feature-a", false);
+    cache.save();
+
+    let cache = TagCache::load(&dir, Verbosity::Quiet);
+    assert_eq!(cache.get("docker.io/library/postgres:16.2"), Some(true));
+    assert_eq!(cache.get("docker.io/mrsarm/app:feature-a"), Some(false));
+    assert_eq!(cache.get("docker.io/mrsarm/app:develop"), None);
+    // another execution stores more entries, the previous are kept
+    let other = TagCache::load(&dir, Verbosity::Quiet);
+    other.insert("docker.io/mrsarm/app:develop", true);
+    other.save();
+    cache.save();
+    let cache = TagCache::load(&dir, Verbosity::Quiet);
+    assert_eq!(cache.get("docker.io/mrsarm/app:develop"), Some(true));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn expired_entries_are_ignored() {
+    let dir = cache_dir("expired");
+    // negative results expire before the positive ones
+    let cache = TagCache::load(&dir, Verbosity::Quiet)
+        .with_ttl(Duration::from_secs(60), Duration::from_secs(0));
+    cache.insert("docker.io/library/postgres:16.2", true);
+    cache.insert("docker.io/mrsarm/app:feature-a", false);
+    assert_eq!(cache.get("docker.io/library/postgres:16.2"), Some(true));
+    assert_eq!(cache.get("docker.io/mrsarm/app:feature-a"), None);
+}
+
+#[test]
+fn invalid_cache_file_is_ignored() {
+    let dir = cache_dir("invalid");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("tags.json"), "{not json").unwrap();
+    let cache = TagCache::load(&dir, Verbosity::Quiet);
+    assert_eq!(cache.get("docker.io/library/postgres:16.2"), None);
+    cache.insert("docker.io/library/postgres:16.2", true);
+    cache.save();
+    let cache = TagCache::load(&dir, Verbosity::Quiet);
+    assert_eq!(cache.get("docker.io/library/postgres:16.2"), Some(true));
+    fs::remove_dir_all(&dir).unwrap();
+}
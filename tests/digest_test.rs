@@ -0,0 +1,36 @@
+use docker_pose::{get_repo_digest, ImageRef};
+use pretty_assertions::assert_eq;
+
+#[test]
+fn get_repo_digest_from_image_inspect() {
+    let inspect = r#"[{
+        "Id": "sha256:f00",
+        "RepoTags": ["localhost:5000/app:1.0", "postgres:15"],
+        "RepoDigests": [
+            "localhost:5000/app@sha256:111",
+            "postgres@sha256:222"
+        ]
+    }]"#;
+    let digest = |image: &str| get_repo_digest(inspect, &ImageRef::parse(image).unwrap());
+    assert_eq!(
+        digest("localhost:5000/app:1.0"),
+        Some("sha256:111".to_string())
+    );
+    assert_eq!(digest("postgres:15"), Some("sha256:222".to_string()));
+    assert_eq!(digest("library/postgres"), Some("sha256:222".to_string()));
+    assert_eq!(
+        digest("index.docker.io/library/postgres:15"),
+        Some("sha256:222".to_string())
+    );
+    assert_eq!(digest("localhost:5001/app:1.0"), None);
+    // images built locally don't have digests
+    let inspect = r#"[{"Id": "sha256:f00", "RepoTags": ["app:1.0"], "RepoDigests": []}]"#;
+    assert_eq!(
+        get_repo_digest(inspect, &ImageRef::parse("app:1.0").unwrap()),
+        None
+    );
+    assert_eq!(
+        get_repo_digest("[]", &ImageRef::parse("app:1.0").unwrap()),
+        None
+    );
+}
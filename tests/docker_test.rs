@@ -64,6 +64,7 @@ fn run_docker_config_multiple_files() {
     let output = command
         .call_compose_config(
             &["tests/compose.yaml", "tests/another.yml"],
+            &[],
             false,
             false,
             true,
@@ -83,7 +84,7 @@ fn run_docker_config_multiple_files() {
 fn run_docker_config_file_not_found() {
     let command = DockerCommand::new(Verbose);
     let output = command
-        .call_compose_config(&["does-not-exist.yml"], false, false, true)
+        .call_compose_config(&["does-not-exist.yml"], &[], false, false, true)
         .unwrap();
     assert!(!output.status.success());
     let stderr = String::from_utf8(output.stderr).unwrap();
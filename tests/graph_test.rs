@@ -0,0 +1,207 @@
+use docker_pose::{ComposeYaml, Dependency, DependencyGraph, PoseError};
+use pretty_assertions::assert_eq;
+use serde_yaml::Error;
+
+#[test]
+fn get_dependencies() -> Result<(), Error> {
+    let compose = ComposeYaml::new(include_str!("compose-depends.yaml"))?;
+    let graph = DependencyGraph::new(&compose);
+    let dependency =
+        |service: &str, depends_on: &str, condition: &str, required: bool| Dependency {
+            service: service.to_string(),
+            depends_on: depends_on.to_string(),
+            condition: condition.to_string(),
+            required,
+        };
+    assert_eq!(
+        graph.get_dependencies()[..3],
+        vec![
+            dependency("web", "api", "service_healthy", true),
+            dependency("web", "cache", "service_started", false),
+            dependency("api", "postgres", "service_started", true),
+        ]
+    );
+    assert_eq!(graph.get_dependencies().len(), 6);
+    Ok(())
+}
+
+#[test]
+fn get_startup_order() -> Result<(), Error> {
+    let compose = ComposeYaml::new(include_str!("compose-depends.yaml"))?;
+    let graph = DependencyGraph::new(&compose);
+    assert_eq!(
+        graph.get_startup_order(),
+        Ok(vec![
+            "postgres".to_string(),
+            "zookeeper".to_string(),
+            "kafka".to_string(),
+            "api".to_string(),
+            "worker".to_string(),
+            "cache".to_string(),
+            "web".to_string(),
+        ])
+    );
+    Ok(())
+}
+
+#[test]
+fn get_startup_order_with_cycle() -> Result<(), Error> {
+    let yaml = "
+services:
+  app:
+    image: app
+    depends_on: [api]
+  api:
+    image: api
+    depends_on: [worker, undefined]
+  worker:
+    image: worker
+    depends_on: [app]
+    ";
+    let compose = ComposeYaml::new(yaml)?;
+    let graph = DependencyGraph::new(&compose);
+    let cycle = vec![
+        "app".to_string(),
+        "api".to_string(),
+        "worker".to_string(),
+        "app".to_string(),
+    ];
+    assert_eq!(graph.find_cycle(), Some(cycle.clone()));
+    assert_eq!(
+        graph.get_startup_order(),
+        Err(PoseError::CircularDependency(cycle))
+    );
+    Ok(())
+}
+
+#[test]
+fn export_dot_and_mermaid() -> Result<(), Error> {
+    let yaml = "
+services:
+  web-app:
+    image: app
+    depends_on:
+      postgres:
+        condition: service_healthy
+      cache:
+        condition: service_started
+        required: false
+  postgres:
+    image: postgres
+  cache:
+    image: redis
+    ";
+    let compose = ComposeYaml::new(yaml)?;
+    let graph = DependencyGraph::new(&compose);
+    let expected_dot = r#"
+digraph services {
+  "web-app";
+  "postgres";
+  "cache";
+  "web-app" -> "postgres" [label="service_healthy"];
+  "web-app" -> "cache" [style=dashed];
+}
+    "#;
+    assert_eq!(expected_dot.trim(), graph.to_dot().trim());
+    let expected_mermaid = r#"
+flowchart TD
+  web_app["web-app"]
+  postgres["postgres"]
+  cache["cache"]
+  web_app -->|service_healthy| postgres
+  web_app -.-> cache
+    "#;
+    assert_eq!(expected_mermaid.trim(), graph.to_mermaid().trim());
+    Ok(())
+}
+
+#[test]
+fn get_related_services() -> Result<(), Error> {
+    let compose = ComposeYaml::new(include_str!("compose-depends.yaml"))?;
+    let graph = DependencyGraph::new(&compose);
+    assert_eq!(
+        graph.get_related_services("web", false, false),
+        Ok(vec!["api".to_string(), "cache".to_string()])
+    );
+    assert_eq!(
+        graph.get_related_services("web", true, false),
+        Ok(vec![
+            "postgres".to_string(),
+            "zookeeper".to_string(),
+            "kafka".to_string(),
+            "api".to_string(),
+            "cache".to_string(),
+        ])
+    );
+    assert_eq!(
+        graph.get_related_services("kafka", false, true),
+        Ok(vec!["api".to_string(), "worker".to_string()])
+    );
+    assert_eq!(
+        graph.get_related_services("kafka", true, true),
+        Ok(vec![
+            "api".to_string(),
+            "web".to_string(),
+            "worker".to_string(),
+        ])
+    );
+    assert_eq!(graph.get_related_services("web", true, true), Ok(vec![]));
+    Ok(())
+}
+
+#[test]
+fn get_related_services_without_duplicates() -> Result<(), Error> {
+    let yaml = "
+services:
+  web:
+    image: web
+    depends_on: [db, cache, db]
+  worker:
+    image: worker
+    depends_on: [db]
+  db:
+    image: postgres
+  cache:
+    image: redis
+";
+    let compose = ComposeYaml::new(yaml)?;
+    let graph = DependencyGraph::new(&compose);
+    assert_eq!(
+        graph.get_related_services("db", false, true),
+        Ok(vec!["web".to_string(), "worker".to_string()])
+    );
+    Ok(())
+}
+
+#[test]
+fn get_related_services_ignores_unrelated_cycles() -> Result<(), Error> {
+    let yaml = "
+services:
+  web:
+    image: web
+    depends_on: [api]
+  api:
+    image: api
+  x:
+    image: x
+    depends_on: [y]
+  y:
+    image: y
+    depends_on: [x]
+    ";
+    let compose = ComposeYaml::new(yaml)?;
+    let graph = DependencyGraph::new(&compose);
+    assert_eq!(
+        graph.get_related_services("web", true, false),
+        Ok(vec!["api".to_string()])
+    );
+    assert_eq!(
+        graph.get_related_services("x", true, false),
+        Err(PoseError::CircularDependency(vec![
+            "x".to_string(),
+            "y".to_string(),
+            "x".to_string(),
+        ]))
+    );
+    Ok(())
+}
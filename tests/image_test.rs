@@ -0,0 +1,107 @@
+use docker_pose::ImageRef;
+use pretty_assertions::assert_eq;
+
+fn image_ref(
+    registry: Option<&str>,
+    port: Option<u16>,
+    repository: &str,
+    tag: Option<&str>,
+    digest: Option<&str>,
+) -> ImageRef {
+    ImageRef {
+        registry: registry.map(String::from),
+        port,
+        repository: repository.to_string(),
+        tag: tag.map(String::from),
+        digest: digest.map(String::from),
+    }
+}
+
+#[test]
+fn parse_image_refs() {
+    let cases = [
+        ("nginx", image_ref(None, None, "nginx", None, None)),
+        (
+            "postgres:16.1",
+            image_ref(None, None, "postgres", Some("16.1"), None),
+        ),
+        (
+            "mrsarm/django-coleman:1.0.1",
+            image_ref(None, None, "mrsarm/django-coleman", Some("1.0.1"), None),
+        ),
+        (
+            "namespace.server.com/image:master",
+            image_ref(
+                Some("namespace.server.com"),
+                None,
+                "image",
+                Some("master"),
+                None,
+            ),
+        ),
+        (
+            "registry:5000/team/app:feature-a",
+            image_ref(
+                Some("registry"),
+                Some(5000),
+                "team/app",
+                Some("feature-a"),
+                None,
+            ),
+        ),
+        (
+            "localhost/app",
+            image_ref(Some("localhost"), None, "app", None, None),
+        ),
+        (
+            "registry:5000/app@sha256:abc123",
+            image_ref(
+                Some("registry"),
+                Some(5000),
+                "app",
+                None,
+                Some("sha256:abc123"),
+            ),
+        ),
+        (
+            "app:1.0@sha256:abc123",
+            image_ref(None, None, "app", Some("1.0"), Some("sha256:abc123")),
+        ),
+    ];
+    for (image, expected) in cases {
+        let parsed = ImageRef::parse(image);
+        assert_eq!(parsed, Ok(expected), "parsing {}", image);
+        assert_eq!(parsed.unwrap().to_string(), image);
+    }
+}
+
+#[test]
+fn parse_invalid_image_refs() {
+    for image in [
+        "",
+        "app:",
+        "registry:port/app",
+        "app@sha256",
+        "app:${TAG}",
+        "app:${TAG:-latest}",
+        "${REGISTRY}/app",
+        "team//app",
+        "app name",
+    ] {
+        assert!(ImageRef::parse(image).is_err(), "parsing {}", image);
+    }
+}
+
+#[test]
+fn image_ref_tags() {
+    let image = ImageRef::parse("registry:5000/app").unwrap();
+    assert_eq!(image.get_tag(), "latest");
+    assert_eq!(image.name(), "registry:5000/app");
+    assert_eq!(
+        image.with_tag("feature-a").to_string(),
+        "registry:5000/app:feature-a"
+    );
+    let image = ImageRef::parse("app@sha256:abc123").unwrap();
+    assert_eq!(image.get_tag(), "");
+    assert_eq!(image.with_tag("1.0").to_string(), "app:1.0");
+}
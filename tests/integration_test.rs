@@ -23,7 +23,7 @@ services:
     ";
     let compose = ComposeYaml::new(&yaml)?;
     let replace_tag = ReplaceTag {
-        tag: "16.2".to_string(),
+        tags: vec!["16.2".to_string()],
         tag_filter: None,
         ignore_unauthorized: true,
         no_slug: false,
@@ -31,15 +31,16 @@ services:
         verbosity: Verbosity::default(),
         progress_verbosity: Verbosity::Quiet,
         threads: 4,
+        cache_dir: None,
     };
     let images = compose.get_images(None, Some(&replace_tag));
     assert_eq!(
         images,
-        Some(vec![
+        Ok(Some(vec![
             "nginx".to_string(),
             "postgres:16.2".to_string(),
             "rabbitmq:3".to_string(),
-        ])
+        ]))
     );
     Ok(())
 }
@@ -56,7 +57,7 @@ services:
     ";
     let compose = ComposeYaml::new(&yaml)?;
     let replace_tag = ReplaceTag {
-        tag: "8".to_string(),
+        tags: vec!["8".to_string()],
         tag_filter: Some((Regex::new(r"mysql").unwrap(), true)),
         ignore_unauthorized: true,
         no_slug: false,
@@ -64,15 +65,16 @@ services:
         verbosity: Verbosity::default(),
         progress_verbosity: Verbosity::Quiet,
         threads: 2,
+        cache_dir: None,
     };
     let images = compose.get_images(None, Some(&replace_tag));
     assert_eq!(
         images,
-        Some(vec![
+        Ok(Some(vec![
             "mysql:8".to_string(),
             // There is postgres:8, but was skipped with the regex filter
             "postgres:7".to_string(),
-        ])
+        ]))
     );
     Ok(())
 }
@@ -99,7 +101,7 @@ services:
     image: rabbitmq
     "#;
     let replace_tag = ReplaceTag {
-        tag: "8 ".to_string(), // the white space will be trimmed when slug is used
+        tags: vec!["8 ".to_string()], // the white space will be trimmed when slug is used
         // Exclude postgres
         tag_filter: Some((Regex::new(r"postgres").unwrap(), false)),
         ignore_unauthorized: true,
@@ -108,9 +110,10 @@ services:
         verbosity: Verbosity::default(),
         progress_verbosity: Verbosity::Quiet,
         threads: 2,
+        cache_dir: None,
     };
     let mut compose = ComposeYaml::new(&yaml)?;
-    compose.update_images_tag(&replace_tag);
+    compose.update_images_tag(&replace_tag).unwrap();
     let new_yaml = compose.to_string();
     assert!(new_yaml.is_ok());
     assert_eq!(expected_yaml.to_string().trim(), new_yaml.unwrap().trim());
@@ -138,7 +141,7 @@ services:
     image: hello-world:linux
     "#;
     let replace_tag = ReplaceTag {
-        tag: "linux".to_string(),
+        tags: vec!["linux".to_string()],
         tag_filter: None,
         ignore_unauthorized: true,
         no_slug: false,
@@ -146,9 +149,10 @@ services:
         verbosity: Verbosity::default(),
         progress_verbosity: Verbosity::Quiet,
         threads: 2,
+        cache_dir: None,
     };
     let mut compose = ComposeYaml::new(&yaml)?;
-    compose.update_images_tag(&replace_tag);
+    compose.update_images_tag(&replace_tag).unwrap();
     let new_yaml = compose.to_string();
     assert!(new_yaml.is_ok());
     assert_eq!(expected_yaml.to_string().trim(), new_yaml.unwrap().trim());
@@ -182,7 +186,7 @@ services:
     image: homeassistant/home-assistant:2024.3
     "#;
     let replace_tag = ReplaceTag {
-        tag: "latest".to_string(),
+        tags: vec!["latest".to_string()],
         tag_filter: None,
         ignore_unauthorized: true,
         no_slug: false,
@@ -190,11 +194,91 @@ services:
         verbosity: Verbosity::default(),
         progress_verbosity: Verbosity::Quiet,
         threads: 2,
+        cache_dir: None,
     };
     let mut compose = ComposeYaml::new(&yaml)?;
-    compose.update_images_tag(&replace_tag);
+    compose.update_images_tag(&replace_tag).unwrap();
     let new_yaml = compose.to_string();
     assert!(new_yaml.is_ok());
     assert_eq!(expected_yaml.to_string().trim(), new_yaml.unwrap().trim());
     Ok(())
 }
+
+#[test]
+#[ignore]
+fn get_config_with_remote_tag_per_service() -> Result<(), Error> {
+    let yaml = r#"
+services:
+  postgres:
+    image: postgres:15
+  postgres-old:
+    image: postgres:9
+    "#;
+    // both services use the same repository, but only
+    // the one that matches the filter has to be updated
+    let expected_yaml = r#"
+services:
+  postgres:
+    image: postgres:16.2
+  postgres-old:
+    image: postgres:9
+    "#;
+    let replace_tag = ReplaceTag {
+        tags: vec!["16.2".to_string()],
+        tag_filter: Some((Regex::new(r"postgres:15").unwrap(), true)),
+        ignore_unauthorized: true,
+        no_slug: false,
+        offline: false,
+        verbosity: Verbosity::default(),
+        progress_verbosity: Verbosity::Quiet,
+        threads: 2,
+        cache_dir: None,
+    };
+    let mut compose = ComposeYaml::new(yaml)?;
+    compose.update_images_tag(&replace_tag).unwrap();
+    let new_yaml = compose.to_string();
+    assert!(new_yaml.is_ok());
+    assert_eq!(expected_yaml.to_string().trim(), new_yaml.unwrap().trim());
+    Ok(())
+}
+
+#[test]
+#[ignore]
+fn get_images_with_remote_tags_fallback() -> Result<(), Error> {
+    let yaml = "
+services:
+  postgres:
+    image: postgres:16.1
+  nginx:
+    image: nginx:1.25
+  rabbitmq:
+    image: rabbitmq:3
+    ";
+    let compose = ComposeYaml::new(yaml)?;
+    // the first tag found for each image is used
+    let replace_tag = ReplaceTag {
+        tags: vec![
+            "feature-not-exists".to_string(),
+            "16.2".to_string(),
+            "1.26".to_string(),
+        ],
+        tag_filter: None,
+        ignore_unauthorized: true,
+        no_slug: false,
+        offline: false,
+        verbosity: Verbosity::default(),
+        progress_verbosity: Verbosity::Quiet,
+        threads: 4,
+        cache_dir: None,
+    };
+    let images = compose.get_images(None, Some(&replace_tag));
+    assert_eq!(
+        images,
+        Ok(Some(vec![
+            "nginx:1.26".to_string(),
+            "postgres:16.2".to_string(),
+            "rabbitmq:3".to_string(),
+        ]))
+    );
+    Ok(())
+}
@@ -0,0 +1,84 @@
+use docker_pose::{ComposeYaml, Interpolator, PoseError, Verbosity};
+use pretty_assertions::assert_eq;
+use serde_yaml::Error;
+use std::collections::HashMap;
+
+fn interpolator(vars: &[(&str, &str)]) -> Interpolator {
+    let vars = vars
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect::<HashMap<_, _>>();
+    Interpolator::new(vars, Verbosity::Quiet)
+}
+
+#[test]
+fn interpolate_compose() -> Result<(), Error> {
+    let yaml = r#"
+services:
+  app:
+    image: "app:${TAG:-latest}"
+    command: [run, --port, "$PORT"]
+    environment:
+      PRICE: "$$10"
+      DB_URL: "postgres://${DB_HOST:-${HOST:-localhost}}:5432"
+    "#;
+    let mut compose = ComposeYaml::new(yaml)?;
+    let result = compose.interpolate(&interpolator(&[("PORT", "8000"), ("HOST", "db")]));
+    assert_eq!(result, Ok(()));
+    let expected = r#"
+services:
+  app:
+    image: app:latest
+    command:
+    - run
+    - --port
+    - '8000'
+    environment:
+      PRICE: $10
+      DB_URL: postgres://db:5432
+    "#;
+    assert_eq!(expected.trim(), compose.to_string()?.trim());
+    Ok(())
+}
+
+#[test]
+fn interpolate_compose_required_variable() -> Result<(), Error> {
+    let yaml = r#"
+services:
+  app:
+    image: app
+    environment:
+      - PORT=${PORT:?port must be set}
+    "#;
+    let mut compose = ComposeYaml::new(yaml)?;
+    let result = compose.interpolate(&interpolator(&[("PORT", "")]));
+    assert_eq!(
+        result,
+        Err(PoseError::Interpolation(
+            "error while interpolating services.app.environment[0]: \
+            required variable PORT is missing a value: port must be set"
+                .to_string()
+        ))
+    );
+    Ok(())
+}
+
+#[test]
+fn interpolate_compose_invalid_format() -> Result<(), Error> {
+    let yaml = r#"
+services:
+  app:
+    image: app:${TAG
+    "#;
+    let mut compose = ComposeYaml::new(yaml)?;
+    let result = compose.interpolate(&interpolator(&[]));
+    assert_eq!(
+        result,
+        Err(PoseError::Interpolation(
+            "error while interpolating services.app.image: \
+            invalid interpolation format for \"app:${TAG\""
+                .to_string()
+        ))
+    );
+    Ok(())
+}
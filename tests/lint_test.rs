@@ -0,0 +1,241 @@
+use docker_pose::{
+    locate_violations, to_sarif, ComposeYaml, LintConfig, Linter, PathSegment, Rule, Severity,
+    Violation,
+};
+use pretty_assertions::assert_eq;
+use serde_yaml::Error;
+
+fn lint(compose: &ComposeYaml) -> Vec<(String, String)> {
+    Linter::default()
+        .lint(compose, &LintConfig::default())
+        .into_iter()
+        .map(|v| (v.rule_id, v.message))
+        .collect()
+}
+
+fn violation(rule_id: &str, message: &str) -> (String, String) {
+    (rule_id.to_string(), message.to_string())
+}
+
+#[test]
+fn lint_compose_file() -> Result<(), Error> {
+    let compose = ComposeYaml::new(include_str!("compose-lint.yaml"))?;
+    assert_eq!(
+        lint(&compose),
+        vec![
+            violation(
+                "image-tag",
+                "service 'app' uses the image 'app:latest' with the latest tag"
+            ),
+            violation(
+                "image-tag",
+                "service 'web' uses the image 'nginx' without a tag"
+            ),
+            violation("privileged", "service 'app' runs in privileged mode"),
+            violation(
+                "port-collision",
+                "port 127.0.0.1:8080:8080/tcp of service 'web' is already \
+                published by service 'app' (8080:80/tcp)"
+            ),
+            violation(
+                "undefined-service",
+                "service 'app' depends on undefined service 'redis'"
+            ),
+            violation(
+                "undefined-volume",
+                "service 'web' uses undefined volume 'static'"
+            ),
+            violation(
+                "undefined-secret",
+                "service 'app' uses undefined secret 'api-key'"
+            ),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn lint_compose_long_syntax() -> Result<(), Error> {
+    // as normalized by docker compose config
+    let compose = ComposeYaml::new(
+        "
+services:
+  app:
+    image: app@sha256:0123456789abcdef
+    depends_on:
+      postgres:
+        condition: service_started
+    networks:
+      default: null
+      backend: null
+    ports:
+      - mode: ingress
+        target: 80
+        published: \"8000\"
+        protocol: tcp
+    volumes:
+      - type: volume
+        source: data
+        target: /data
+      - type: bind
+        source: /tmp
+        target: /tmp
+    configs:
+      - source: app-config
+        target: /etc/app.conf
+  postgres:
+    image: postgres:15
+    ports:
+      - 8000-8010:5432-5442/tcp
+volumes:
+  data: {}
+",
+    )?;
+    assert_eq!(
+        lint(&compose),
+        vec![
+            violation(
+                "port-collision",
+                "port 8000-8010:5432-5442/tcp of service 'postgres' is already \
+                published by service 'app' (8000:80/tcp)"
+            ),
+            violation(
+                "undefined-network",
+                "service 'app' uses undefined network 'backend'"
+            ),
+            violation(
+                "undefined-config",
+                "service 'app' uses undefined config 'app-config'"
+            ),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn lint_with_config() -> Result<(), Error> {
+    let compose = ComposeYaml::new(include_str!("compose-lint.yaml"))?;
+    let config = LintConfig::parse(
+        "
+disable:
+  - image-tag
+  - port-collision
+  - not-a-rule
+files:
+  compose-lint.yaml:
+    disable: [privileged]
+",
+    )?;
+    let linter = Linter::default();
+    assert_eq!(config.get_unknown_rules(&linter), vec!["not-a-rule"]);
+    let mut violations = linter.lint(&compose, &config);
+    assert_eq!(violations.len(), 4);
+    let files = vec![(
+        "tests/compose-lint.yaml".to_string(),
+        include_str!("compose-lint.yaml").to_string(),
+    )];
+    locate_violations(&mut violations, &files);
+    violations.retain(|v| !config.is_disabled(&v.rule_id, v.file.as_deref()));
+    assert_eq!(
+        violations
+            .iter()
+            .map(|v| (v.line, v.to_string()))
+            .collect::<Vec<_>>(),
+        vec![
+            (
+                Some(9),
+                "error [undefined-service] service 'app' depends on undefined service 'redis'"
+                    .to_string()
+            ),
+            (
+                Some(19),
+                "error [undefined-volume] service 'web' uses undefined volume 'static'".to_string()
+            ),
+            (
+                Some(13),
+                "error [undefined-secret] service 'app' uses undefined secret 'api-key'"
+                    .to_string()
+            ),
+        ]
+    );
+    assert!(LintConfig::parse("disabled: [image-tag]").is_err());
+    Ok(())
+}
+
+#[test]
+fn locate_violations_in_last_file() {
+    let mut violations = vec![Violation {
+        rule_id: "image-tag".to_string(),
+        severity: Severity::Warning,
+        message: "service 'app' uses the image 'app' without a tag".to_string(),
+        path: vec![
+            PathSegment::Key("services".to_string()),
+            PathSegment::Key("app".to_string()),
+            PathSegment::Key("image".to_string()),
+        ],
+        file: None,
+        line: None,
+    }];
+    let files = vec![
+        (
+            "compose.yaml".to_string(),
+            "services:\n  app:\n    image: app:1.0\n".to_string(),
+        ),
+        (
+            "compose.override.yaml".to_string(),
+            "services:\n  postgres:\n    image: postgres\n  app:\n    image: app\n".to_string(),
+        ),
+    ];
+    locate_violations(&mut violations, &files);
+    assert_eq!(
+        violations[0].file,
+        Some("compose.override.yaml".to_string())
+    );
+    assert_eq!(violations[0].line, Some(5));
+}
+
+struct NoBuildRule;
+
+impl Rule for NoBuildRule {
+    fn id(&self) -> &'static str {
+        "no-build"
+    }
+
+    fn description(&self) -> &'static str {
+        "Services should not be built"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Info
+    }
+
+    fn check(&self, compose: &ComposeYaml) -> Vec<Violation> {
+        compose
+            .get_root_element_names("services")
+            .into_iter()
+            .filter(|name| compose.get_service(name).unwrap().contains_key("build"))
+            .map(|name| self.violation(vec![], format!("service '{}' is built", name)))
+            .collect()
+    }
+}
+
+#[test]
+fn lint_with_custom_rule_to_sarif() -> Result<(), Error> {
+    let compose = ComposeYaml::new("services:\n  app:\n    build: .\n")?;
+    let mut linter = Linter::new(vec![]);
+    linter.add_rule(Box::new(NoBuildRule));
+    let violations = linter.lint(&compose, &LintConfig::default());
+    let sarif = to_sarif(&linter, &violations);
+    assert_eq!(sarif["version"], "2.1.0");
+    let run = &sarif["runs"][0];
+    assert_eq!(run["tool"]["driver"]["name"], "pose");
+    assert_eq!(run["tool"]["driver"]["rules"][0]["id"], "no-build");
+    assert_eq!(run["results"][0]["ruleId"], "no-build");
+    assert_eq!(run["results"][0]["level"], "note");
+    assert_eq!(
+        run["results"][0]["message"]["text"],
+        "service 'app' is built"
+    );
+    assert!(run["results"][0].get("locations").is_none());
+    Ok(())
+}
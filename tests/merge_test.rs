@@ -0,0 +1,171 @@
+use docker_pose::ComposeYaml;
+use pretty_assertions::assert_eq;
+use serde_yaml::Error;
+
+#[test]
+fn merge_services() -> Result<(), Error> {
+    let base = "
+services:
+  app1:
+    image: some-image
+    command: [run, --port, '8000']
+  postgres:
+    image: postgres:15
+volumes:
+  data:
+    driver: local
+    ";
+    let other = "
+services:
+  app1:
+    image: some-image:2.0
+    command: [run]
+  nginx:
+    image: nginx
+networks:
+  front-tier: {}
+    ";
+    let mut compose = ComposeYaml::new(base)?;
+    compose.merge(ComposeYaml::new(other)?);
+    let expected = "
+networks:
+  front-tier: {}
+services:
+  app1:
+    image: some-image:2.0
+    command:
+    - run
+  postgres:
+    image: postgres:15
+  nginx:
+    image: nginx
+volumes:
+  data:
+    driver: local
+    ";
+    assert_eq!(expected.trim(), compose.to_string()?.trim());
+    Ok(())
+}
+
+#[test]
+fn merge_sequences() -> Result<(), Error> {
+    let base = "
+services:
+  app:
+    image: app
+    ports:
+      - 8000:8000
+    volumes:
+      - data:/var/lib/data
+      - ./conf:/etc/conf:ro
+    dns:
+      - 8.8.8.8
+    healthcheck:
+      test: [CMD, curl, -f, http://localhost]
+    ";
+    let other = "
+services:
+  app:
+    ports:
+      - 8000:8000
+      - 9000:9000
+    volumes:
+      - ./conf-ci:/etc/conf:ro
+    dns:
+      - 1.1.1.1
+    healthcheck:
+      test: [CMD, wget, http://localhost]
+    ";
+    let mut compose = ComposeYaml::new(base)?;
+    compose.merge(ComposeYaml::new(other)?);
+    let expected = "
+services:
+  app:
+    image: app
+    ports:
+    - 8000:8000
+    - 9000:9000
+    volumes:
+    - data:/var/lib/data
+    - ./conf-ci:/etc/conf:ro
+    dns:
+    - 8.8.8.8
+    - 1.1.1.1
+    healthcheck:
+      test:
+      - CMD
+      - wget
+      - http://localhost
+    ";
+    assert_eq!(expected.trim(), compose.to_string()?.trim());
+    Ok(())
+}
+
+#[test]
+fn merge_envs() -> Result<(), Error> {
+    let base = "
+services:
+  app1:
+    environment:
+      - PORT=8000
+      - DEBUG=false
+  app2:
+    environment:
+      PORT: 9000
+    ";
+    let other = "
+services:
+  app1:
+    environment:
+      - DEBUG=true
+      - TITLE=App 1
+  app2:
+    environment:
+      - PORT=9001
+      - DEBUG
+    ";
+    let mut compose = ComposeYaml::new(base)?;
+    compose.merge(ComposeYaml::new(other)?);
+    let app1 = compose.get_service("app1").expect("app1 not found");
+    assert_eq!(
+        compose.get_service_envs(app1).unwrap_or_default(),
+        vec!["PORT=8000", "DEBUG=true", "TITLE=App 1"]
+    );
+    let app2 = compose.get_service("app2").expect("app2 not found");
+    assert_eq!(
+        compose.get_service_envs(app2).unwrap_or_default(),
+        vec!["PORT=9001", "DEBUG=null"]
+    );
+    Ok(())
+}
+
+#[test]
+fn merge_reset_and_override() -> Result<(), Error> {
+    let base = "
+services:
+  app:
+    image: app
+    ports:
+      - 8000:8000
+    labels:
+      a: '1'
+    ";
+    let other = "
+services:
+  app:
+    ports: !reset []
+    labels: !override
+      b: '2'
+    ";
+    let mut compose = ComposeYaml::new(base)?;
+    compose.merge(ComposeYaml::new(other)?);
+    let expected = "
+services:
+  app:
+    image: app
+    labels:
+      b: '2'
+    ";
+    assert_eq!(expected.trim(), compose.to_string()?.trim());
+    Ok(())
+}
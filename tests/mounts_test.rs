@@ -0,0 +1,107 @@
+use docker_pose::{find_mount_issues, ComposeYaml, Mount, MountType};
+use pretty_assertions::assert_eq;
+use serde_yaml::Error;
+use std::path::Path;
+
+#[test]
+fn get_mounts() -> Result<(), Error> {
+    let compose = ComposeYaml::new(include_str!("compose-mounts.yaml"))?;
+    let mounts = compose.get_mounts();
+    assert_eq!(
+        mounts
+            .iter()
+            .map(|m| (
+                m.service.as_str(),
+                m.mount.mount_type.to_string(),
+                m.mount.to_string()
+            ))
+            .collect::<Vec<_>>(),
+        vec![
+            (
+                "app",
+                "bind".to_string(),
+                "./compose.yaml:/app/compose.yaml:ro,cached".to_string()
+            ),
+            ("app", "bind".to_string(), "./not-exists:/data".to_string()),
+            ("app", "volume".to_string(), "app-cache:/cache".to_string()),
+            ("app", "volume".to_string(), "undeclared:/other".to_string()),
+            ("app", "volume".to_string(), "/var/lib/anon".to_string()),
+            ("app", "tmpfs".to_string(), "/run".to_string()),
+            ("app", "tmpfs".to_string(), "/tmp".to_string()),
+            (
+                "db",
+                "volume".to_string(),
+                "db-data:/var/lib/postgresql/data".to_string()
+            ),
+            (
+                "db",
+                "bind".to_string(),
+                "./compose.yaml:/etc/compose.yaml:ro,delegated".to_string()
+            ),
+            ("db", "image".to_string(), "models:1.0:/models".to_string()),
+        ]
+    );
+    assert_eq!(
+        compose.get_invalid_mounts(),
+        vec![(
+            "db".to_string(),
+            "invalid volume type 'unknown'".to_string()
+        )]
+    );
+    let app = compose.get_service("app").expect("app not found");
+    assert_eq!(
+        compose.get_service_named_volumes(app),
+        vec!["app-cache", "undeclared"]
+    );
+    Ok(())
+}
+
+#[test]
+fn find_mounts_issues() -> Result<(), Error> {
+    let compose = ComposeYaml::new(include_str!("compose-mounts.yaml"))?;
+    let mounts = compose.get_mounts();
+    let volumes = compose.get_root_element_names("volumes");
+    let issues = find_mount_issues(&mounts, &volumes, Path::new("tests"))
+        .into_iter()
+        .map(|(m, issue)| (m.mount.target.as_str(), issue))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        issues,
+        vec![
+            (
+                "/data",
+                "host path tests/not-exists doesn't exist".to_string()
+            ),
+            ("/other", "volume 'undeclared' not declared".to_string()),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn parse_mounts() {
+    assert_eq!(
+        Mount::parse("data:/data:nocopy").unwrap(),
+        Mount {
+            mount_type: MountType::Volume,
+            source: Some("data".to_string()),
+            target: "/data".to_string(),
+            read_only: false,
+            consistency: None,
+        }
+    );
+    let mount = Mount::parse("${DATA_DIR}:/data").unwrap();
+    assert_eq!(mount.mount_type, MountType::Bind);
+    assert_eq!(mount.get_host_path(Path::new(".")), None);
+    let mount = Mount::parse("C:\\data:/data:rw").unwrap();
+    assert_eq!(mount.source, Some("C:\\data".to_string()));
+    assert_eq!(mount.mount_type, MountType::Bind);
+    assert!(Mount::parse("a:b:c:d").is_err());
+    assert!(Mount::parse("data:").is_err());
+    let volume = serde_yaml::from_str("{type: image, source: app:1.0, target: /data}").unwrap();
+    let mount = Mount::from_value(&volume).unwrap();
+    assert_eq!(mount.mount_type, MountType::Image);
+    assert_eq!(mount.source, Some("app:1.0".to_string()));
+    let volume = serde_yaml::from_str("{type: unknown, target: /data}").unwrap();
+    assert!(Mount::from_value(&volume).is_err());
+}
@@ -0,0 +1,35 @@
+use docker_pose::{Netrc, NetrcEntry};
+use pretty_assertions::assert_eq;
+
+#[test]
+fn parse_netrc_multiline_entries() {
+    let netrc = Netrc::parse(
+        "machine gitlab.com\n\
+         \tlogin ci-bot\n\
+         \tpassword glpat-123\n\
+         \n\
+         machine nexus.local login deploy account ops password n3xus\n",
+    );
+    assert_eq!(
+        netrc.get("gitlab.com"),
+        Some(&NetrcEntry {
+            login: "ci-bot".to_string(),
+            password: "glpat-123".to_string(),
+        })
+    );
+    assert_eq!(netrc.get("nexus.local").unwrap().password, "n3xus");
+    assert_eq!(netrc.get("github.com"), None);
+}
+
+#[test]
+fn parse_netrc_skips_macros() {
+    let netrc = Netrc::parse(
+        "macdef init\n\
+         machine fake.com login fake password fake\n\
+         \n\
+         machine nexus.local login deploy password n3xus\n\
+         default login anonymous password guest\n",
+    );
+    assert_eq!(netrc.get("fake.com").unwrap().login, "anonymous");
+    assert_eq!(netrc.get("nexus.local").unwrap().login, "deploy");
+}
@@ -1,6 +1,7 @@
-use docker_pose::ComposeYaml;
+use docker_pose::{ComposeYaml, EnvVar, PoseError, ProfileServices, ServiceImage};
 use pretty_assertions::assert_eq;
 use serde_yaml::Error;
+use std::path::Path;
 
 #[test]
 fn get_services_list() -> Result<(), Error> {
@@ -224,6 +225,74 @@ services:
     Ok(())
 }
 
+#[test]
+fn get_profiles_services() -> Result<(), Error> {
+    let compose = ComposeYaml::new(include_str!("compose-profiles.yaml"))?;
+    let profile = |profile: &str, services: &[&str]| ProfileServices {
+        profile: profile.to_string(),
+        services: services.iter().map(|s| s.to_string()).collect(),
+    };
+    assert_eq!(
+        compose.get_profiles_services(),
+        vec![
+            profile("debug", &["adminer", "mailhog"]),
+            profile("tools", &["adminer", "provision"]),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn filter_profiles() -> Result<(), Error> {
+    let mut compose = ComposeYaml::new(include_str!("compose-profiles.yaml"))?;
+    compose.filter_profiles(&["debug".to_string()]);
+    assert_eq!(
+        compose.get_root_element_names("services"),
+        vec!["app", "postgres", "adminer", "mailhog"]
+    );
+    let mut compose = ComposeYaml::new(include_str!("compose-profiles.yaml"))?;
+    compose.filter_profiles(&["not-a-profile".to_string()]);
+    assert_eq!(
+        compose.get_root_element_names("services"),
+        vec!["app", "postgres"]
+    );
+    let mut compose = ComposeYaml::new(include_str!("compose-profiles.yaml"))?;
+    compose.filter_profiles(&["*".to_string()]);
+    assert_eq!(compose.get_root_element_names("services").len(), 5);
+    Ok(())
+}
+
+#[test]
+fn filter_profiles_removes_references() -> Result<(), Error> {
+    let yaml = "
+services:
+  web:
+    image: web
+    depends_on: [debug, db]
+  db:
+    image: postgres
+  debug:
+    image: debug
+    profiles: [dev]
+    volumes: [debug-data:/data]
+volumes:
+  debug-data:
+";
+    let mut compose = ComposeYaml::new(yaml)?;
+    compose.filter_profiles(&["ops".to_string()]);
+    assert_eq!(
+        compose.get_root_element_names("services"),
+        vec!["web", "db"]
+    );
+    let web = compose.get_service("web").expect("web not found");
+    assert_eq!(
+        compose.get_service_depends_on(web),
+        Some(vec!["db".to_string()])
+    );
+    assert!(compose.get_root_element("volumes").is_none());
+    Ok(())
+}
+
 #[test]
 fn get_profiles_none() -> Result<(), Error> {
     let yaml = "
@@ -264,13 +333,13 @@ services:
     let images = compose.get_images(None, None);
     assert_eq!(
         images,
-        Some(vec![
+        Ok(Some(vec![
             "app".to_string(),     // used twice, but included once
             "app:1.0".to_string(), // same image but with different version
             "namespace.server.com/image:master".to_string(),
             "nginx:stable".to_string(),
             "postgres:16.1".to_string(),
-        ])
+        ]))
     );
     Ok(())
 }
@@ -296,10 +365,68 @@ services:
     let images = compose.get_images(Some("master"), None);
     assert_eq!(
         images,
-        Some(vec![
+        Ok(Some(vec![
             "namespace.server.com/image:master".to_string(),
             "nginx:master".to_string(),
-        ])
+        ]))
+    );
+    Ok(())
+}
+
+#[test]
+fn get_service_images() -> Result<(), Error> {
+    let yaml = "
+services:
+  app0: the-app
+  app:
+    image: app
+  psql:
+    image: postgres:16.1
+  app-provision:
+    image: app
+  builder:
+    build: .
+    ";
+    let compose = ComposeYaml::new(yaml)?;
+    let images = compose.get_service_images(None, None);
+    let service_image = |service: &str, image: &str| ServiceImage {
+        service: service.to_string(),
+        image: image.to_string(),
+        resolved: image.to_string(),
+    };
+    assert_eq!(
+        images,
+        Ok(Some(vec![
+            service_image("app", "app"),
+            service_image("psql", "postgres:16.1"),
+            service_image("app-provision", "app"),
+        ]))
+    );
+    Ok(())
+}
+
+#[test]
+fn get_images_filter_by_tag_with_registry_port() -> Result<(), Error> {
+    let yaml = "
+services:
+  app:
+    image: registry:5000/app
+  web:
+    image: registry:5000/web:master
+  psql:
+    image: postgres:master@sha256:abc123
+    ";
+    let compose = ComposeYaml::new(yaml)?;
+    assert_eq!(
+        compose.get_images(Some("master"), None),
+        Ok(Some(vec![
+            "postgres:master@sha256:abc123".to_string(),
+            "registry:5000/web:master".to_string(),
+        ]))
+    );
+    assert_eq!(
+        compose.get_images(Some("latest"), None),
+        Ok(Some(vec!["registry:5000/app".to_string()]))
     );
     Ok(())
 }
@@ -313,7 +440,7 @@ volumes:
     ";
     let compose = ComposeYaml::new(&yaml)?;
     let images = compose.get_images(None, None);
-    assert!(images.is_none());
+    assert_eq!(images, Ok(None));
     Ok(())
 }
 
@@ -408,3 +535,222 @@ services:
     assert!(depends_on.is_none());
     Ok(())
 }
+
+#[test]
+fn get_service_envs_with_env_files() -> Result<(), Error> {
+    let compose = ComposeYaml::new(include_str!("compose-env-files.yaml"))?;
+    let app = compose.get_service("app").expect("app not found");
+    let envs = compose
+        .get_service_env_vars_with_files(app, Path::new("tests"))
+        .expect("error reading env files");
+    assert_eq!(
+        envs,
+        vec![
+            EnvVar::new("PORT", Some("8000"), "environment"),
+            EnvVar::new("DEBUG", Some("true"), "app-ci.env"),
+            EnvVar::new("TITLE", Some("App Title"), "app.env"),
+        ]
+    );
+    assert_eq!(
+        envs.iter().map(|e| e.to_string()).collect::<Vec<_>>(),
+        vec!["PORT=8000", "DEBUG=true", "TITLE=App Title"]
+    );
+    Ok(())
+}
+
+#[test]
+fn get_service_envs_with_env_files_without_value() -> Result<(), Error> {
+    let yaml = "
+services:
+  app:
+    image: app
+    env_file: app.env
+    environment:
+      - PORT
+      - POSE_TEST_FROM_HOST
+      - POSE_TEST_UNDEFINED
+    ";
+    std::env::set_var("POSE_TEST_FROM_HOST", "host value");
+    let compose = ComposeYaml::new(yaml)?;
+    let app = compose.get_service("app").expect("app not found");
+    let envs = compose
+        .get_service_env_vars_with_files(app, Path::new("tests"))
+        .expect("error reading env files");
+    assert_eq!(
+        envs.iter().map(|e| e.to_string()).collect::<Vec<_>>(),
+        vec![
+            "PORT=3000",
+            "DEBUG=false",
+            "TITLE=App Title",
+            "POSE_TEST_FROM_HOST=host value",
+            "POSE_TEST_UNDEFINED=",
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn get_service_envs_with_env_files_not_found() -> Result<(), Error> {
+    let yaml = "
+services:
+  app:
+    image: app
+    env_file: does-not-exist.env
+    ";
+    let compose = ComposeYaml::new(yaml)?;
+    let app = compose.get_service("app").expect("app not found");
+    let envs = compose.get_service_env_vars_with_files(app, Path::new("tests"));
+    assert_eq!(
+        envs,
+        Err(PoseError::ReadFile(
+            "env file tests/does-not-exist.env not found".to_string()
+        ))
+    );
+    Ok(())
+}
+
+#[test]
+fn filter_services_with_deps() -> Result<(), Error> {
+    let mut compose = ComposeYaml::new(include_str!("compose-depends.yaml"))?;
+    compose
+        .filter_services(&["web".to_string()], true)
+        .expect("services not found");
+    assert_eq!(
+        compose.get_root_element_names("services"),
+        vec!["web", "api", "postgres", "kafka", "zookeeper", "cache"]
+    );
+    let mut compose = ComposeYaml::new(include_str!("compose-depends.yaml"))?;
+    compose
+        .filter_services(&["api".to_string(), "worker".to_string()], false)
+        .expect("services not found");
+    assert_eq!(
+        compose.get_root_element_names("services"),
+        vec!["api", "worker"]
+    );
+    // the references to the services removed are dropped
+    let api = compose.get_service("api").expect("api not found");
+    assert_eq!(compose.get_service_depends_on(api), None);
+    assert_eq!(
+        compose.filter_services(&["nope".to_string()], false),
+        Err(PoseError::ServiceNotFound("nope".to_string()))
+    );
+    Ok(())
+}
+
+#[test]
+fn filter_services_with_references() -> Result<(), Error> {
+    let yaml = "
+services:
+  api:
+    image: api
+    network_mode: service:vpn
+    volumes_from: [data, 'container:legacy']
+    links: ['cache:redis']
+  vpn:
+    image: vpn
+  data:
+    image: data
+  cache:
+    image: redis
+  web:
+    image: web
+";
+    let mut compose = ComposeYaml::new(yaml)?;
+    compose
+        .filter_services(&["api".to_string()], true)
+        .expect("api not found");
+    assert_eq!(
+        compose.get_root_element_names("services"),
+        vec!["api", "vpn", "data", "cache"]
+    );
+    let mut compose = ComposeYaml::new(yaml)?;
+    compose
+        .filter_services(&["api".to_string()], false)
+        .expect("api not found");
+    let api = compose.get_service("api").expect("api not found");
+    assert!(!api.contains_key("network_mode"));
+    assert!(!api.contains_key("links"));
+    // the containers are not services, so they are kept
+    assert_eq!(
+        api.get("volumes_from"),
+        Some(&serde_yaml::from_str("['container:legacy']")?)
+    );
+    Ok(())
+}
+
+#[test]
+fn filter_services_removes_resources_not_used() -> Result<(), Error> {
+    let yaml = "
+services:
+  app:
+    image: app
+    depends_on:
+      db:
+        condition: service_started
+    networks: [front]
+    volumes:
+      - app-data:/data
+      - ./src:/src
+    configs: [app-config]
+    build:
+      context: .
+      secrets: [npm-token]
+  db:
+    image: postgres
+    networks: [back]
+    volumes:
+      - type: volume
+        source: db-data
+        target: /var/lib/postgresql/data
+    secrets:
+      - source: db-password
+volumes:
+  app-data:
+  db-data:
+networks:
+  default:
+    name: project
+  front:
+  back:
+configs:
+  app-config:
+    file: ./app.conf
+secrets:
+  npm-token:
+    environment: NPM_TOKEN
+  db-password:
+    file: ./db-password.txt
+";
+    let mut compose = ComposeYaml::new(yaml)?;
+    compose
+        .filter_services(&["app".to_string()], false)
+        .expect("app not found");
+    assert_eq!(compose.get_root_element_names("services"), vec!["app"]);
+    assert_eq!(compose.get_root_element_names("volumes"), vec!["app-data"]);
+    assert_eq!(
+        compose.get_root_element_names("networks"),
+        vec!["default", "front"]
+    );
+    assert_eq!(
+        compose.get_root_element_names("configs"),
+        vec!["app-config"]
+    );
+    assert_eq!(compose.get_root_element_names("secrets"), vec!["npm-token"]);
+    let app = compose.get_service("app").expect("app not found");
+    assert!(!app.contains_key("depends_on"));
+    let mut compose = ComposeYaml::new(yaml)?;
+    compose
+        .filter_services(&["db".to_string()], false)
+        .expect("db not found");
+    assert_eq!(compose.get_root_element_names("volumes"), vec!["db-data"]);
+    assert_eq!(
+        compose.get_root_element_names("configs"),
+        Vec::<&str>::new()
+    );
+    assert!(compose.get_root_element("configs").is_none());
+    assert_eq!(
+        compose.get_root_element_names("secrets"),
+        vec!["db-password"]
+    );
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use docker_pose::{find_port_collisions, ComposeYaml, PortMapping, PortRange, ServicePort};
+use pretty_assertions::assert_eq;
+use serde_yaml::Error;
+
+#[test]
+fn parse_port_mappings() {
+    let port = PortMapping::parse("127.0.0.1::80").unwrap();
+    assert_eq!(port.host_ip, Some("127.0.0.1".to_string()));
+    assert_eq!(port.published, None);
+    assert_eq!(port.to_string(), "127.0.0.1::80/tcp");
+    let port = PortMapping::parse("[::1]:8080:80/udp").unwrap();
+    assert_eq!(port.to_string(), "[::1]:8080:80/udp");
+    let port = PortMapping::from_value(&serde_yaml::Value::from(3000)).unwrap();
+    assert_eq!(port.target, PortRange::new(3000, 3000));
+    assert!(PortMapping::parse("81-80:80").is_err());
+    assert!(PortMapping::parse("1:2:3:4").is_err());
+    // the same port in different interfaces
+    assert!(!PortMapping::parse("127.0.0.1:80:80")
+        .unwrap()
+        .collides_with(&PortMapping::parse("192.168.0.1:80:80").unwrap()));
+}
+
+#[test]
+fn get_ports() -> Result<(), Error> {
+    let compose = ComposeYaml::new(include_str!("compose-ports.yaml"))?;
+    let ports = compose.get_ports();
+    assert_eq!(
+        ports
+            .iter()
+            .map(|p| (p.service.as_str(), p.index, p.port.to_string()))
+            .collect::<Vec<_>>(),
+        vec![
+            ("web", 0, "80:80/tcp".to_string()),
+            ("web", 1, "443:443/tcp".to_string()),
+            ("web", 2, "127.0.0.1:8080:8080/tcp".to_string()),
+            ("dns", 0, "53:53/udp".to_string()),
+            ("dns", 1, "53:53/tcp".to_string()),
+            ("api", 0, "[::1]:8000-8010:3000/tcp".to_string()),
+            ("api", 1, "9229/tcp".to_string()),
+            // the port with a variable not interpolated is skipped
+            ("proxy", 0, "[::]:443:8443/tcp".to_string()),
+        ]
+    );
+    assert_eq!(
+        ports[5].port,
+        PortMapping {
+            host_ip: Some("::1".to_string()),
+            published: Some(PortRange::new(8000, 8010)),
+            target: PortRange::new(3000, 3000),
+            protocol: "tcp".to_string(),
+        }
+    );
+    Ok(())
+}
+
+#[test]
+fn find_ports_collisions() -> Result<(), Error> {
+    let compose = ComposeYaml::new(include_str!("compose-ports.yaml"))?;
+    let ports = compose.get_ports();
+    let collisions = find_port_collisions(&ports)
+        .into_iter()
+        .map(|(port, other)| (port.service.as_str(), port.index, other.service.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(collisions, vec![("proxy", 0, "web")]);
+    Ok(())
+}
+
+#[test]
+fn serialize_ports() -> Result<(), Error> {
+    let port = ServicePort {
+        port: PortMapping::parse("127.0.0.1:8000-8001:80-81/udp").unwrap(),
+        service: "app".to_string(),
+        index: 0,
+    };
+    assert_eq!(
+        serde_yaml::to_string(&port)?,
+        "host_ip: 127.0.0.1\npublished: 8000-8001\ntarget: 80-81\nprotocol: udp\nservice: app\n"
+    );
+    Ok(())
+}
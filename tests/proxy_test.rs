@@ -0,0 +1,56 @@
+use docker_pose::ProxyConfig;
+use pretty_assertions::assert_eq;
+use std::env;
+
+const VARS: [&str; 4] = ["http_proxy", "https_proxy", "all_proxy", "no_proxy"];
+
+fn clear_env() {
+    for var in VARS {
+        env::remove_var(var);
+        env::remove_var(var.to_uppercase());
+    }
+}
+
+/// All the cases in one test, as the environment is shared by the tests.
+#[test]
+fn proxy_config_from_env() {
+    clear_env();
+    assert_eq!(ProxyConfig::from_env(), ProxyConfig::default());
+
+    env::set_var("HTTPS_PROXY", "http://proxy.corp:3128");
+    env::set_var("ALL_PROXY", "http://all.corp:3128");
+    env::set_var("NO_PROXY", "localhost,.internal.corp,nexus.corp:8443");
+    let proxy = ProxyConfig::from_env();
+    assert_eq!(
+        proxy,
+        ProxyConfig {
+            http: Some("http://all.corp:3128".to_string()),
+            https: Some("http://proxy.corp:3128".to_string()),
+            no_proxy: vec![
+                "localhost".to_string(),
+                "internal.corp".to_string(),
+                "nexus.corp".to_string(),
+            ],
+        }
+    );
+    assert_eq!(
+        proxy.get_proxy("https://nexus.corp:8443/compose.yaml"),
+        None
+    );
+    assert_eq!(
+        proxy.get_proxy("https://git.internal.corp/compose.yaml"),
+        None
+    );
+    assert_eq!(
+        proxy.get_proxy("https://gitlab.com/compose.yaml"),
+        Some("http://proxy.corp:3128")
+    );
+
+    // the lowercase variables have precedence, and the empty ones are ignored
+    env::set_var("https_proxy", "http://lower.corp:3128");
+    env::set_var("http_proxy", "");
+    let proxy = ProxyConfig::from_env();
+    assert_eq!(proxy.https, Some("http://lower.corp:3128".to_string()));
+    assert_eq!(proxy.http, Some("http://all.corp:3128".to_string()));
+    clear_env();
+}
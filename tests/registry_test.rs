@@ -0,0 +1,282 @@
+use docker_pose::{
+    read_docker_auths, read_docker_insecure_registries, ComposeYaml, ImageRef, RegistryClient,
+    RegistryError, ReplaceTag, TagCache, Verbosity,
+};
+use pretty_assertions::assert_eq;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+
+/// base64 of "user:pass"
+const CREDENTIALS: &str = "dXNlcjpwYXNz";
+const TOKEN: &str = "secret-token";
+const DIGEST: &str = "sha256:b5b2b2c507a0944348e0303114d8d93aaaa081732b86451d9bce1f432a537bc7";
+
+/// Start a registry that requires a bearer token to access the
+/// `team/app` repository, where only the tag `1.0` exists, and
+/// that allows anonymous access to the `public` repository.
+fn mock_registry() -> u16 {
+    mock_registry_on("127.0.0.1")
+}
+
+fn mock_registry_on(ip: &str) -> u16 {
+    let listener = TcpListener::bind(format!("{}:0", ip)).unwrap();
+    let port = listener.local_addr().unwrap().port();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            handle(stream, port);
+        }
+    });
+    port
+}
+
+fn handle(mut stream: TcpStream, port: u16) {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut request_line = String::new();
+    // e.g. a TLS handshake, when the client tries HTTPS first
+    if reader.read_line(&mut request_line).is_err() {
+        return;
+    }
+    let mut authorization = None;
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        if line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("authorization") {
+                authorization = Some(value.trim().to_string());
+            }
+        }
+    }
+    let path = request_line.split(' ').nth(1).unwrap_or_default();
+    let bearer = format!("Bearer {}", TOKEN);
+    let basic = format!("Basic {}", CREDENTIALS);
+    let (status, headers, body) = match path {
+        "/v2/public/manifests/latest" => (
+            "200 OK",
+            format!("Docker-Content-Digest: {}\r\n", DIGEST),
+            String::new(),
+        ),
+        p if p.starts_with("/token?") => match authorization.as_deref() {
+            Some(auth) if auth == basic && p.contains("scope=repository%3Ateam%2Fapp%3Apull") => (
+                "200 OK",
+                String::new(),
+                format!("{{\"token\": \"{}\"}}", TOKEN),
+            ),
+            _ => ("401 Unauthorized", String::new(), String::new()),
+        },
+        p if p.starts_with("/v2/team/app/manifests/") => match authorization.as_deref() {
+            Some(auth) if auth == bearer && p.ends_with("/1.0") => {
+                ("200 OK", String::new(), String::new())
+            }
+            Some(auth) if auth == bearer => ("404 Not Found", String::new(), String::new()),
+            _ => (
+                "401 Unauthorized",
+                format!(
+                    "WWW-Authenticate: Bearer realm=\"http://127.0.0.1:{}/token\",\
+                    service=\"mock\",scope=\"repository:team/app:pull\"\r\n",
+                    port
+                ),
+                String::new(),
+            ),
+        },
+        _ => ("404 Not Found", String::new(), String::new()),
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        headers,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).unwrap();
+}
+
+fn image(port: u16, name: &str) -> ImageRef {
+    ImageRef::parse(&format!("127.0.0.1:{}/{}", port, name)).unwrap()
+}
+
+#[test]
+fn has_manifest_with_bearer_token() {
+    let port = mock_registry();
+    let auths = HashMap::from([(
+        format!("http://127.0.0.1:{}/v2/", port),
+        CREDENTIALS.to_string(),
+    )]);
+    let client = RegistryClient::new(auths, vec![], Verbosity::Quiet);
+    assert_eq!(client.has_manifest(&image(port, "team/app:1.0")), Ok(true));
+    // the token is reused
+    assert_eq!(client.has_manifest(&image(port, "team/app:2.0")), Ok(false));
+    assert_eq!(client.has_manifest(&image(port, "public")), Ok(true));
+}
+
+#[test]
+fn get_digest_of_manifest() {
+    let port = mock_registry();
+    let client = RegistryClient::new(HashMap::new(), vec![], Verbosity::Quiet);
+    assert_eq!(
+        client.get_digest(&image(port, "public")),
+        Ok(Some(DIGEST.to_string()))
+    );
+    assert_eq!(client.get_digest(&image(port, "private")), Ok(None));
+}
+
+#[test]
+fn has_manifest_unauthorized() {
+    let port = mock_registry();
+    let client = RegistryClient::new(HashMap::new(), vec![], Verbosity::Quiet);
+    let result = client.has_manifest(&image(port, "team/app:1.0"));
+    assert!(matches!(result, Err(RegistryError::Unauthorized(_))));
+}
+
+#[test]
+fn has_manifest_without_credentials_over_plain_http() {
+    // 127.0.0.2 is in the loopback interface, but it's not
+    // treated as a loopback registry, like docker does
+    let port = mock_registry_on("127.0.0.2");
+    let host = format!("127.0.0.2:{}", port);
+    let auths = HashMap::from([(host.clone(), CREDENTIALS.to_string())]);
+    let client = RegistryClient::new(auths, vec![host], Verbosity::Quiet);
+    let image = ImageRef::parse(&format!("127.0.0.2:{}/team/app:1.0", port)).unwrap();
+    assert_eq!(
+        client.has_manifest(&image),
+        Err(RegistryError::Unauthorized(format!(
+            "credentials are not sent to 127.0.0.2:{} over plain HTTP",
+            port
+        )))
+    );
+    // anonymous access is allowed
+    let image = ImageRef::parse(&format!("127.0.0.2:{}/public", port)).unwrap();
+    assert_eq!(client.has_manifest(&image), Ok(true));
+}
+
+#[test]
+fn has_manifest_over_plain_http_only_with_insecure_registries() {
+    let port = mock_registry_on("127.0.0.2");
+    let image = ImageRef::parse(&format!("127.0.0.2:{}/public", port)).unwrap();
+    let client = RegistryClient::new(HashMap::new(), vec![], Verbosity::Quiet);
+    assert!(matches!(
+        client.has_manifest(&image),
+        Err(RegistryError::Transport(_))
+    ));
+    let insecure_registries = vec![format!("127.0.0.2:{}", port)];
+    let client = RegistryClient::new(HashMap::new(), insecure_registries, Verbosity::Quiet);
+    assert_eq!(client.has_manifest(&image), Ok(true));
+}
+
+#[test]
+fn unauthorized_errors_ignored_are_not_cached() {
+    let port = mock_registry();
+    let dir = env::temp_dir().join(format!("pose-registry-test-cache-{}", port));
+    // without credentials, and only the remote registry is checked
+    env::set_var("DOCKER_CONFIG", &dir);
+    env::set_var("DOCKER_BIN", "pose-test-docker-not-found");
+    let yaml = format!(
+        "
+services:
+  app:
+    image: 127.0.0.1:{port}/team/app:0.9
+  public:
+    image: 127.0.0.1:{port}/public:0.9
+    "
+    );
+    let compose = ComposeYaml::new(&yaml).unwrap();
+    let replace_tag = ReplaceTag {
+        tags: vec!["1.0".to_string()],
+        tag_filter: None,
+        ignore_unauthorized: true,
+        no_slug: false,
+        offline: false,
+        verbosity: Verbosity::Quiet,
+        progress_verbosity: Verbosity::Quiet,
+        threads: 2,
+        cache_dir: Some(dir.clone()),
+    };
+    let images = compose.get_images(None, Some(&replace_tag));
+    assert_eq!(
+        images,
+        Ok(Some(vec![
+            format!("127.0.0.1:{}/public:0.9", port),
+            format!("127.0.0.1:{}/team/app:0.9", port),
+        ]))
+    );
+    let cache = TagCache::load(&dir, Verbosity::Quiet);
+    let _ = fs::remove_dir_all(&dir);
+    assert_eq!(
+        cache.get(&format!("127.0.0.1:{}/public:1.0", port)),
+        Some(false)
+    );
+    assert_eq!(cache.get(&format!("127.0.0.1:{}/team/app:1.0", port)), None);
+}
+
+#[test]
+fn has_manifest_connection_refused() {
+    // get a free port, and close it
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let client = RegistryClient::new(HashMap::new(), vec![], Verbosity::Quiet);
+    let result = client.has_manifest(&image(port, "team/app:1.0"));
+    assert!(matches!(result, Err(RegistryError::Transport(_))));
+}
+
+#[test]
+fn read_docker_config_auths() {
+    let path = env::temp_dir().join("pose-registry-test-config.json");
+    fs::write(
+        &path,
+        r#"{
+            "auths": {
+                "https://index.docker.io/v1/": {"auth": "aHViOnB3ZA=="},
+                "registry.example.com": {"auth": "dXNlcjpwYXNz"},
+                "ghcr.io": {}
+            },
+            "credsStore": "desktop"
+        }"#,
+    )
+    .unwrap();
+    let auths = read_docker_auths(&path);
+    fs::remove_file(&path).unwrap();
+    assert_eq!(
+        auths,
+        Ok(HashMap::from([
+            (
+                "https://index.docker.io/v1/".to_string(),
+                "aHViOnB3ZA==".to_string()
+            ),
+            (
+                "registry.example.com".to_string(),
+                "dXNlcjpwYXNz".to_string()
+            ),
+        ]))
+    );
+}
+
+#[test]
+fn read_docker_daemon_config_insecure_registries() {
+    let path = env::temp_dir().join("pose-registry-test-daemon.json");
+    fs::write(
+        &path,
+        r#"{
+            "insecure-registries": ["registry.local:5000", "10.0.0.0/8", "http://registry.lan"],
+            "debug": true
+        }"#,
+    )
+    .unwrap();
+    let registries = read_docker_insecure_registries(&path);
+    fs::remove_file(&path).unwrap();
+    assert_eq!(
+        registries,
+        Ok(vec![
+            "registry.local:5000".to_string(),
+            "registry.lan".to_string()
+        ])
+    );
+}
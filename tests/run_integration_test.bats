@@ -1,12 +1,19 @@
 setup() {
     load 'test_helper/bats-support/load'
     load 'test_helper/bats-assert/load'
+    # a new tags cache for each test, so the registry is always checked
+    export XDG_CACHE_HOME="$BATS_TEST_TMPDIR"
 }
 
 teardown_file() {
     rm -f get-never.yaml
     rm -f ci-check.yaml
     rm -f compose-remote-check.yaml
+    rm -f get-retry.yaml
+    rm -f get-fallback.yaml
+    rm -f get-checksum.yaml
+    rm -f get-cached.yaml
+    rm -f .*.pose-cache
 }
 
 @test "can list images with remote tag" {
@@ -15,13 +22,13 @@ teardown_file() {
     assert_success
     assert_output --partial "DEBUG: docker compose -f tests/compose-remote-check.yaml config"
     # django-mongotail:3.1.1 is checked, and it's found
-    assert_output --partial "DEBUG: docker manifest inspect --insecure mrsarm/mongotail:3.1.1"
+    assert_output --partial "DEBUG: HEAD https://registry-1.docker.io/v2/mrsarm/mongotail/manifests/3.1.1"
     assert_output --partial "DEBUG: manifest for image mrsarm/mongotail:3.1.1 ... found"
     # django-coleman:3.1.1 is checked, although it doesn't exist in the docker registry
-    assert_output --partial "DEBUG: docker manifest inspect --insecure mrsarm/django-coleman:3.1.1"
+    assert_output --partial "DEBUG: HEAD https://registry-1.docker.io/v2/mrsarm/django-coleman/manifests/3.1.1"
     refute_output --partial "DEBUG: manifest for image mrsarm/django-coleman:3.1.1 ... found"
     # bitnami/kafka:3.1.1 exists, but is NOT checked because the filter
-    refute_output --partial "DEBUG: docker manifest inspect --insecure bitnami/kafka:3.1.1"
+    refute_output --partial "DEBUG: HEAD https://registry-1.docker.io/v2/bitnami/kafka/manifests/3.1.1"
     assert_output --partial "DEBUG: manifest for image bitnami/kafka ... skipped"
 
     # Output
@@ -37,13 +44,13 @@ teardown_file() {
     assert_success
     assert_output --partial "DEBUG: docker compose -f tests/compose-remote-check.yaml config"
     # django-mongotail:3.1.1 is checked, and it's found
-    assert_output --partial "DEBUG: docker manifest inspect --insecure mrsarm/mongotail:3.1.1"
+    assert_output --partial "DEBUG: HEAD https://registry-1.docker.io/v2/mrsarm/mongotail/manifests/3.1.1"
     assert_output --partial "DEBUG: manifest for image mrsarm/mongotail:3.1.1 ... found"
     # django-coleman:3.1.1 is checked, although it doesn't exist in the docker registry
-    assert_output --partial "DEBUG: docker manifest inspect --insecure mrsarm/django-coleman:3.1.1"
+    assert_output --partial "DEBUG: HEAD https://registry-1.docker.io/v2/mrsarm/django-coleman/manifests/3.1.1"
     assert_output --partial "DEBUG: manifest for image mrsarm/django-coleman:3.1.1 ... not found"
     # bitnami/kafka:3.1.1 exists, but is NOT checked because the filter
-    refute_output --partial "DEBUG: docker manifest inspect --insecure bitnami/kafka:3.1.1"
+    refute_output --partial "DEBUG: HEAD https://registry-1.docker.io/v2/bitnami/kafka/manifests/3.1.1"
     assert_output --partial "DEBUG: manifest for image bitnami/kafka ... skipped"
 
     # Output
@@ -53,6 +60,54 @@ teardown_file() {
     assert_output --partial "image: mrsarm/mongotail:3.1.1"
 }
 
+@test "can list images with remote tag without docker CLI" {
+    DOCKER_BIN=not-a-docker-bin run target/debug/pose --verbose --no-docker \
+        -f tests/compose-remote-check.yaml list images --tag 3.1.1 --tag-filter "regex=mrsarm/"
+    assert_success
+    assert_output --partial "DEBUG: not-a-docker-bin not found, skipping local check of image mrsarm/mongotail:3.1.1"
+    assert_output --partial "DEBUG: manifest for image mrsarm/mongotail:3.1.1 ... found"
+    assert_output --partial "mrsarm/mongotail:3.1.1"
+}
+
+@test "can list images with remote tag from cache" {
+    run target/debug/pose -f tests/compose-remote-check.yaml \
+        list images --tag 3.1.1 --tag-filter "regex=mrsarm/mongotail" --progress
+    assert_success
+    assert_output --partial "DEBUG: manifest for image mrsarm/mongotail:3.1.1 ... found"
+    refute_output --partial "(cached)"
+    run target/debug/pose -f tests/compose-remote-check.yaml \
+        list images --tag 3.1.1 --tag-filter "regex=mrsarm/mongotail" --progress
+    assert_success
+    assert_output --partial "DEBUG: manifest for image mrsarm/mongotail:3.1.1 ... found (cached)"
+    assert_output --partial "mrsarm/mongotail:3.1.1"
+}
+
+@test "can list images with a fallback chain of remote tags" {
+    run target/debug/pose --verbose -f tests/compose-remote-check.yaml \
+        list images --tag not-a-tag --tag 3.1.1,3.1.0 --tag-filter "regex=mrsarm/"
+    assert_success
+    # mongotail:not-a-tag is checked first, then 3.1.1 is found
+    assert_output --partial "DEBUG: HEAD https://registry-1.docker.io/v2/mrsarm/mongotail/manifests/not-a-tag"
+    assert_output --partial "DEBUG: image mrsarm/mongotail:3.1 resolved with tag 3.1.1 (candidate 2 of 3)"
+    refute_output --partial "DEBUG: HEAD https://registry-1.docker.io/v2/mrsarm/mongotail/manifests/3.1.0"
+    # no tag found for django-coleman
+    assert_output --partial "DEBUG: image mrsarm/django-coleman:1.0.1 ... no tag candidate found"
+
+    # Output
+    assert_output --partial "mrsarm/django-coleman:1.0.1"
+    assert_output --partial "mrsarm/mongotail:3.1.1"
+}
+
+@test "can output config with images pinned to digests" {
+    run target/debug/pose -f tests/compose-remote-check.yaml config --pin-digests --progress
+    assert_success
+    assert_output --partial "DEBUG: digest for image mrsarm/mongotail:3.1 ... sha256:"
+    assert_output --partial "image: mrsarm/mongotail@sha256:"
+    assert_output --partial "pose.image: mrsarm/mongotail:3.1"
+    assert_output --partial "image: bitnami/kafka@sha256:"
+    assert_output --partial "pose.image: bitnami/kafka:3.0"
+}
+
 @test "can get a file with another name" {
     run target/debug/pose get https://raw.githubusercontent.com/mrsarm/pose/main/tests/compose-remote-check.yaml -o ci-check.yaml
     assert_success
@@ -89,3 +144,41 @@ teardown_file() {
     assert_output --partial "DEBUG: Downloading https://raw.githubusercontent.com/mrsarm/pose/not-exist-as-well/tests/compose-remote-check.yaml ... not found"
     assert_output --partial "ERROR: Download failed"
 }
+
+@test "can get a file with retries" {
+    run target/debug/pose get --retry 2 --retry-delay 1 https://raw.githubusercontent.com/mrsarm/pose/main/tests/compose-remote-check.yaml -o get-retry.yaml
+    assert_success
+    assert_output --partial "... found"
+    [ -f get-retry.yaml ]
+}
+
+@test "can get a file from a URL template trying fallbacks in order" {
+    run target/debug/pose get "https://raw.githubusercontent.com/mrsarm/pose/{slug}/tests/compose-remote-check.yaml" \
+        --fallback never-exist --fallback main -o get-fallback.yaml
+    assert_success
+    # the current branch may not exist in the repo, but main does
+    assert_output --partial "DEBUG: Downloading https://raw.githubusercontent.com/mrsarm/pose/main/tests/compose-remote-check.yaml ... found"
+    [ -f get-fallback.yaml ]
+}
+
+@test "can't get a file if the checksum doesn't match" {
+    run target/debug/pose get https://raw.githubusercontent.com/mrsarm/pose/main/tests/compose-remote-check.yaml \
+        --sha256 0000000000000000000000000000000000000000000000000000000000000000 -o get-checksum.yaml
+    assert_failure 25
+    assert_output --partial "ERROR: checksum mismatch of the file downloaded from"
+    [ ! -f get-checksum.yaml ]
+    run ls .get-checksum.yaml.*.part
+    assert_failure
+}
+
+@test "can get a file only if modified" {
+    run target/debug/pose get https://raw.githubusercontent.com/mrsarm/pose/main/tests/compose-remote-check.yaml -o get-cached.yaml
+    assert_success
+    [ -f .get-cached.yaml.pose-cache ]
+    run target/debug/pose get https://raw.githubusercontent.com/mrsarm/pose/main/tests/compose-remote-check.yaml -o get-cached.yaml
+    assert_success
+    assert_output --partial "DEBUG: Downloading https://raw.githubusercontent.com/mrsarm/pose/main/tests/compose-remote-check.yaml ... not modified"
+    run target/debug/pose get --no-cache https://raw.githubusercontent.com/mrsarm/pose/main/tests/compose-remote-check.yaml -o get-cached.yaml
+    assert_success
+    assert_output --partial "DEBUG: Downloading https://raw.githubusercontent.com/mrsarm/pose/main/tests/compose-remote-check.yaml ... found"
+}
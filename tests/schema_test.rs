@@ -0,0 +1,150 @@
+use docker_pose::{
+    find_line, validate_compose_file, Interpolator, PathSegment, PoseError, Verbosity,
+};
+use pretty_assertions::assert_eq;
+use std::collections::HashMap;
+
+fn path(segments: &[&str]) -> Vec<PathSegment> {
+    segments
+        .iter()
+        .map(|s| match s.parse::<usize>() {
+            Ok(i) => PathSegment::Index(i),
+            Err(_) => PathSegment::Key(s.to_string()),
+        })
+        .collect()
+}
+
+#[test]
+fn validate_compose_valid_files() -> Result<(), PoseError> {
+    for content in [
+        include_str!("compose.yaml"),
+        include_str!("compose-depends.yaml"),
+        include_str!("compose-env-files.yaml"),
+        include_str!("another.yml"),
+    ] {
+        assert_eq!(validate_compose_file(content, None)?, vec![]);
+    }
+    Ok(())
+}
+
+#[test]
+fn validate_compose_invalid_file() -> Result<(), PoseError> {
+    let errors = validate_compose_file(include_str!("compose-invalid.yaml"), None)?;
+    assert_eq!(
+        errors
+            .iter()
+            .map(|e| (e.line, e.to_string()))
+            .collect::<Vec<_>>(),
+        vec![
+            (
+                Some(7),
+                "services.app.ports[1].publishd: additional property not allowed".to_string()
+            ),
+            (
+                Some(10),
+                "services.app.depends_on.postgres.condition: must be one of: \
+                \"service_started\", \"service_healthy\", \"service_completed_successfully\""
+                    .to_string()
+            ),
+            (
+                Some(12),
+                "services.postgres.image: must be a string, but got a list".to_string()
+            ),
+            (
+                Some(13),
+                "services.postgres.restart: must be a string, but got an integer".to_string()
+            ),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn validate_compose_with_extensions_and_merge_keys() -> Result<(), PoseError> {
+    let yaml = "
+x-defaults: &defaults
+  restart: always
+services:
+  app:
+    <<: *defaults
+    image: app
+    x-owner: team
+  'my app':
+    image: app
+";
+    let errors = validate_compose_file(yaml, None)?;
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].path, path(&["services", "my app"]));
+    assert_eq!(errors[0].line, Some(9));
+    Ok(())
+}
+
+#[test]
+fn validate_compose_interpolated() -> Result<(), PoseError> {
+    let yaml = "
+services:
+  app:
+    image: app
+    cpu_count: ${CPUS}
+    oom_score_adj: ${SCORE}
+";
+    let vars = HashMap::from([
+        ("CPUS".to_string(), "2".to_string()),
+        ("SCORE".to_string(), "-2000".to_string()),
+    ]);
+    let interpolator = Interpolator::new(vars, Verbosity::Quiet);
+    // the interpolated values are strings, as docker compose does
+    assert_eq!(validate_compose_file(yaml, Some(&interpolator))?, vec![]);
+    let errors = validate_compose_file("services: app", None)?;
+    assert_eq!(
+        errors[0].to_string(),
+        "services: must be a mapping, but got a string"
+    );
+    Ok(())
+}
+
+#[test]
+fn validate_compose_not_yaml() {
+    let result = validate_compose_file("services:\n  - a\n b: c", None);
+    assert!(matches!(result, Err(PoseError::ParseYaml(_))));
+}
+
+#[test]
+fn find_line_in_block_collections() {
+    let yaml = "# comment
+services:
+  app:
+    image: app
+    ports:
+    - 80:80
+    - target: 443
+
+      published: 8443
+    environment: {A: 1}
+  \"web\":
+    image: web
+";
+    assert_eq!(find_line(yaml, &path(&["services"])), Some(2));
+    assert_eq!(
+        find_line(yaml, &path(&["services", "app", "ports", "0"])),
+        Some(6)
+    );
+    assert_eq!(
+        find_line(yaml, &path(&["services", "app", "ports", "1", "target"])),
+        Some(7)
+    );
+    assert_eq!(
+        find_line(yaml, &path(&["services", "app", "ports", "1", "published"])),
+        Some(9)
+    );
+    // values in flow style are located in the line of the collection
+    assert_eq!(
+        find_line(yaml, &path(&["services", "app", "environment", "A"])),
+        Some(10)
+    );
+    assert_eq!(
+        find_line(yaml, &path(&["services", "web", "image"])),
+        Some(12)
+    );
+    assert_eq!(find_line(yaml, &path(&["volumes"])), None);
+}